@@ -4,7 +4,7 @@ use test::Bencher;
 extern crate num_complex;
 extern crate utils;
 use std::fmt;
-use utils::{constants, constraints, pricing_maps};
+use utils::{constraints, pricing_maps};
 #[bench]
 fn bench_option_price_merton_u_128(b: &mut Bencher) {
     b.iter(|| {
@@ -24,14 +24,19 @@ fn bench_option_price_merton_u_128(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Merton(parameters),
-            10.0,
-            128,
+            &pricing_maps::PricingParameters {
+                num_u: 128,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -56,14 +61,19 @@ fn bench_option_price_merton_u_256(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Merton(parameters),
-            10.0,
-            256,
+            &pricing_maps::PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -88,14 +98,19 @@ fn bench_option_price_merton_u_512(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Merton(parameters),
-            10.0,
-            512,
+            &pricing_maps::PricingParameters {
+                num_u: 512,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -120,14 +135,19 @@ fn bench_option_price_merton_u_1024(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Merton(parameters),
-            10.0,
-            1024,
+            &pricing_maps::PricingParameters {
+                num_u: 1024,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -150,14 +170,19 @@ fn bench_option_price_heston_u_128(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Heston(parameters),
-            10.0,
-            128,
+            &pricing_maps::PricingParameters {
+                num_u: 128,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -179,14 +204,19 @@ fn bench_option_price_heston_u_256(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Heston(parameters),
-            10.0,
-            256,
+            &pricing_maps::PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -208,14 +238,19 @@ fn bench_option_price_heston_u_512(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Heston(parameters),
-            10.0,
-            512,
+            &pricing_maps::PricingParameters {
+                num_u: 512,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -237,14 +272,19 @@ fn bench_option_price_heston_u_1024(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Heston(parameters),
-            10.0,
-            1024,
+            &pricing_maps::PricingParameters {
+                num_u: 1024,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -271,14 +311,19 @@ fn bench_option_price_cgmy_u_128(b: &mut Bencher) {
             rho: -0.1,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::CGMY(parameters),
-            10.0,
-            128,
+            &pricing_maps::PricingParameters {
+                num_u: 128,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -304,14 +349,19 @@ fn bench_option_price_cgmy_u_256(b: &mut Bencher) {
             rho: -0.1,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::CGMY(parameters),
-            10.0,
-            256,
+            &pricing_maps::PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -337,14 +387,19 @@ fn bench_option_price_cgmy_u_512(b: &mut Bencher) {
             rho: -0.1,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::CGMY(parameters),
-            10.0,
-            512,
+            &pricing_maps::PricingParameters {
+                num_u: 512,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -370,14 +425,19 @@ fn bench_option_price_cgmy_u_1024(b: &mut Bencher) {
             rho: -0.1,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::CGMY(parameters),
-            10.0,
-            1024,
+            &pricing_maps::PricingParameters {
+                num_u: 1024,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -429,14 +489,19 @@ fn bench_option_price_merton_strike_one(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Merton(parameters),
-            10.0,
-            256,
+            &pricing_maps::PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -461,14 +526,19 @@ fn bench_option_price_merton_strike_seven(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Merton(parameters),
-            10.0,
-            256,
+            &pricing_maps::PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -493,14 +563,19 @@ fn bench_option_price_merton_strike_sixty(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Merton(parameters),
-            10.0,
-            256,
+            &pricing_maps::PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -523,14 +598,19 @@ fn bench_option_price_heston_one_strike(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Heston(parameters),
-            10.0,
-            256,
+            &pricing_maps::PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -552,14 +632,19 @@ fn bench_option_price_heston_seven_strike(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Heston(parameters),
-            10.0,
-            256,
+            &pricing_maps::PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -581,14 +666,19 @@ fn bench_option_price_heston_sixty_strike(b: &mut Bencher) {
             rho: -0.5,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::Heston(parameters),
-            10.0,
-            256,
+            &pricing_maps::PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -614,14 +704,19 @@ fn bench_option_price_cgmy_one_strike(b: &mut Bencher) {
             rho: -0.1,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::CGMY(parameters),
-            10.0,
-            256,
+            &pricing_maps::PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -646,14 +741,19 @@ fn bench_option_price_cgmy_seven_strike(b: &mut Bencher) {
             rho: -0.1,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::CGMY(parameters),
-            10.0,
-            256,
+            &pricing_maps::PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -678,14 +778,19 @@ fn bench_option_price_cgmy_sixty_strike(b: &mut Bencher) {
             rho: -0.1,
         };
         pricing_maps::get_option_results_as_json(
-            constants::CALL_PRICE,
-            false,
+            pricing_maps::OptionKind::Call,
+            pricing_maps::Sensitivity::Price,
+            pricing_maps::ImpliedVolatilityMode::Skip,
             &constraints::CFParameters::CGMY(parameters),
-            10.0,
-            256,
+            &pricing_maps::PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -5,8 +5,12 @@ use rocket::serde::json::{json, Error as JsonError, Value};
 use std::env;
 const OPTION_SCALE: f64 = 10.0;
 const DENSITY_SCALE: f64 = 5.0;
+const JOB_TTL_SECONDS: u64 = 3600;
+use rocket::response::status;
 use rocket::tokio::task;
-use utils::{constants, constraints, pricing_maps};
+use rocket::State;
+use std::time::Duration;
+use utils::{calibration, constants, constraints, jobs, pricing_maps};
 #[get("/<model>/parameters/parameter_ranges")]
 pub async fn parameters(model: &str) -> Value {
     match model {
@@ -14,111 +18,403 @@ pub async fn parameters(model: &str) -> Value {
         constants::CGMY_NAME => json!(constraints::CGMY_CONSTRAINTS),
         constants::CGMYSE_NAME => json!(constraints::CGMYSE_CONSTRAINTS),
         constants::MERTON_NAME => json!(constraints::MERTON_CONSTRAINTS),
+        constants::KOU_NAME => json!(constraints::KOU_CONSTRAINTS),
+        constants::BLACK_SCHOLES_NAME => json!(constraints::BLACK_SCHOLES_CONSTRAINTS),
+        constants::BATES_NAME => json!(constraints::BATES_CONSTRAINTS),
+        constants::VG_NAME => json!(constraints::VG_CONSTRAINTS),
+        constants::NIG_NAME => json!(constraints::NIG_CONSTRAINTS),
         _ => json!(constraints::PARAMETER_CONSTRAINTS),
     }
 }
 
 #[post(
-    "/<_>/calculator/<option_type>/<sensitivity>?<include_implied_volatility>",
-    data = "<parameters>"
+    "/<model>/calculator/<option_type>/<sensitivity>?<options..>",
+    data = "<parameters>",
+    rank = 2 //after greeks
 )]
 pub async fn calculator(
-    option_type: &str,
-    sensitivity: &str,
-    parameters: Result<Json<constraints::OptionParameters>, JsonError<'_>>,
+    model: &str,
+    option_type: Result<pricing_maps::OptionKind, constraints::ParameterError>,
+    sensitivity: Result<pricing_maps::Sensitivity, constraints::ParameterError>,
+    parameters: Result<Json<Value>, JsonError<'_>>,
+    options: constraints::CalculatorOptions,
+) -> Result<Json<pricing_maps::CalculatorOutput>, constraints::ParameterError> {
+    let option_type = option_type?;
+    let sensitivity = sensitivity?;
+    let parameters: constraints::OptionParameters =
+        constraints::parse_model_parameters(model, parameters?.into_inner())?;
+    constraints::check_parameters(&parameters, &constraints::PARAMETER_CONSTRAINTS)?;
+    let constraints::OptionParameters {
+        maturity,
+        rates,
+        asset,
+        num_u: num_u_base,
+        tolerance,
+        strikes,
+        truncation_scale,
+        cf_parameters,
+        ..
+    } = parameters; //destructure
+
+    let strikes_unwrap = strikes.ok_or(constraints::throw_no_exist_error("strikes"))?;
+    let asset_unwrap = asset.ok_or(constraints::throw_no_exist_error("asset"))?;
+
+    //auto refines its own grid
+    let num_u = num_u_base
+        .exponent()
+        .map_or(0, |num_u| 2_usize.pow(num_u as u32));
+    let (pricing, effective_rate) = pricing_maps::get_pricing_parameters(
+        &rates,
+        Some(asset_unwrap),
+        maturity,
+        num_u,
+        truncation_scale.unwrap_or(OPTION_SCALE),
+    )?;
+    let iv_mode = pricing_maps::ImpliedVolatilityMode::new(
+        options.include_implied_volatility.unwrap_or(false),
+        options.strict_implied_volatility.unwrap_or(false),
+    );
+    let check_arbitrage = options.check_arbitrage.unwrap_or(false);
+    let tolerance = tolerance.unwrap_or_default(); //required for auto, checked above
+    let response = task::spawn_blocking(move || {
+        let response = match num_u_base {
+            constraints::NumU::Exponent(num_u) => pricing_maps::CalculatorResponse {
+                results: pricing_maps::get_option_results_as_json(
+                    option_type,
+                    sensitivity,
+                    iv_mode,
+                    &cf_parameters,
+                    &pricing,
+                    asset_unwrap,
+                    &strikes_unwrap,
+                )?,
+                num_u,
+                converged: true,
+                arbitrage_violations: None,
+            },
+            constraints::NumU::Auto(_) => {
+                let response = pricing_maps::get_adaptive_option_results_as_json(
+                    option_type,
+                    sensitivity,
+                    &cf_parameters,
+                    &pricing,
+                    tolerance,
+                    asset_unwrap,
+                    &strikes_unwrap,
+                )?;
+                //implied volatilities from the final values
+                pricing_maps::CalculatorResponse {
+                    results: pricing_maps::get_implied_volatility_results(
+                        option_type,
+                        sensitivity,
+                        iv_mode,
+                        &pricing,
+                        asset_unwrap,
+                        response.results,
+                    )?,
+                    ..response
+                }
+            }
+        };
+        let arbitrage_violations = if check_arbitrage {
+            //put prices are also checked against the calls
+            let puts: Option<Vec<f64>> = match (option_type, sensitivity) {
+                (pricing_maps::OptionKind::Put, pricing_maps::Sensitivity::Price) => Some(
+                    response
+                        .results
+                        .iter()
+                        .map(|element| element.value)
+                        .collect(),
+                ),
+                _ => None,
+            };
+            Some(pricing_maps::get_arbitrage_violations_as_json(
+                &cf_parameters,
+                &pricing_maps::PricingParameters {
+                    num_u: 2_usize.pow(response.num_u as u32),
+                    ..pricing
+                },
+                asset_unwrap,
+                &strikes_unwrap,
+                puts.as_deref(),
+            )?)
+        } else {
+            None
+        };
+        Ok::<_, constraints::ParameterError>(pricing_maps::CalculatorResponse {
+            results: pricing_maps::with_effective_rate(response.results, effective_rate),
+            arbitrage_violations,
+            ..response
+        })
+    })
+    .await??;
+    Ok(Json(match num_u_base {
+        constraints::NumU::Exponent(_) if !check_arbitrage => {
+            pricing_maps::CalculatorOutput::Results(response.results)
+        }
+        _ => pricing_maps::CalculatorOutput::Response(response),
+    }))
+}
+
+#[post(
+    "/<model>/calculator/<option_type>/greeks?<sensitivities>&<include_implied_volatility>&<strict_implied_volatility>",
+    data = "<parameters>"
+)]
+pub async fn greeks(
+    model: &str,
+    option_type: Result<pricing_maps::OptionKind, constraints::ParameterError>,
+    parameters: Result<Json<Value>, JsonError<'_>>,
+    sensitivities: Option<&str>,
     include_implied_volatility: Option<bool>,
-) -> Result<Json<Vec<pricing_maps::GraphElement>>, constraints::ParameterError> {
-    let parameters = parameters?;
-    let fn_indicator = pricing_maps::get_fn_indicators(option_type, sensitivity)?;
+    strict_implied_volatility: Option<bool>,
+) -> Result<Json<Vec<pricing_maps::GreeksElement>>, constraints::ParameterError> {
+    let option_type = option_type?;
+    let parameters: constraints::OptionParameters =
+        constraints::parse_model_parameters(model, parameters?.into_inner())?;
     constraints::check_parameters(&parameters, &constraints::PARAMETER_CONSTRAINTS)?;
     let constraints::OptionParameters {
         maturity,
-        rate,
+        rates,
         asset,
         num_u: num_u_base,
         strikes,
+        truncation_scale,
         cf_parameters,
         ..
-    } = parameters.into_inner(); //destructure
+    } = parameters; //destructure
 
     let strikes_unwrap = strikes.ok_or(constraints::throw_no_exist_error("strikes"))?;
     let asset_unwrap = asset.ok_or(constraints::throw_no_exist_error("asset"))?;
 
-    let num_u = (2 as usize).pow(num_u_base as u32);
-    let include_iv = include_implied_volatility.unwrap_or(false);
+    let (pricing, effective_rate) = pricing_maps::get_pricing_parameters(
+        &rates,
+        Some(asset_unwrap),
+        maturity,
+        2_usize.pow(num_u_base.exponent()? as u32),
+        truncation_scale.unwrap_or(OPTION_SCALE),
+    )?;
+    let selection = pricing_maps::GreeksSelection::new(
+        sensitivities,
+        pricing_maps::ImpliedVolatilityMode::new(
+            include_implied_volatility.unwrap_or(false),
+            strict_implied_volatility.unwrap_or(false),
+        ),
+    )?;
     let results = task::spawn_blocking(move || {
-        pricing_maps::get_option_results_as_json(
-            fn_indicator,
-            include_iv,
+        pricing_maps::get_greeks_results_as_json(
+            option_type,
+            &selection,
             &cf_parameters,
-            OPTION_SCALE,
-            num_u,
+            &pricing,
             asset_unwrap,
-            maturity,
-            rate,
             &strikes_unwrap,
         )
     })
     .await??;
-    Ok(Json(results))
+    Ok(Json(
+        results
+            .into_iter()
+            .map(|element| pricing_maps::GreeksElement {
+                effective_rate,
+                ..element
+            })
+            .collect(),
+    ))
 }
 
-#[post("/<_>/density", data = "<parameters>")]
+#[post("/<model>/density", data = "<parameters>")]
 pub async fn density(
-    parameters: Result<Json<constraints::OptionParameters>, JsonError<'_>>,
+    model: &str,
+    parameters: Result<Json<Value>, JsonError<'_>>,
 ) -> Result<Json<Vec<pricing_maps::GraphElement>>, constraints::ParameterError> {
-    let parameters = parameters?;
+    let parameters: constraints::OptionParameters =
+        constraints::parse_model_parameters(model, parameters?.into_inner())?;
     constraints::check_parameters(&parameters, &constraints::PARAMETER_CONSTRAINTS)?;
 
     let constraints::OptionParameters {
         maturity,
-        rate,
+        rates,
+        asset,
         num_u: num_u_base,
+        truncation_scale,
         cf_parameters,
         ..
-    } = parameters.into_inner(); //destructure
+    } = parameters; //destructure
 
-    let num_u = (2 as usize).pow(num_u_base as u32);
+    let (pricing, effective_rate) = pricing_maps::get_pricing_parameters(
+        &rates,
+        asset,
+        maturity,
+        2_usize.pow(num_u_base.exponent()? as u32),
+        truncation_scale.unwrap_or(DENSITY_SCALE),
+    )?;
     let results = task::spawn_blocking(move || {
-        pricing_maps::get_density_results_as_json(
-            &cf_parameters,
-            DENSITY_SCALE,
-            num_u,
-            maturity,
-            rate,
-        )
+        pricing_maps::get_density_results_as_json(&cf_parameters, &pricing)
     })
     .await??;
 
-    Ok(Json(results))
+    Ok(Json(pricing_maps::with_effective_rate(
+        results,
+        effective_rate,
+    )))
 }
 
-#[post("/<_>/riskmetric", data = "<parameters>")]
+#[post("/<model>/riskmetric", data = "<parameters>")]
 pub async fn risk_metric(
-    parameters: Result<Json<constraints::OptionParameters>, JsonError<'_>>,
-) -> Result<Json<cf_dist_utils::RiskMetric>, constraints::ParameterError> {
-    let parameters = parameters?;
+    model: &str,
+    parameters: Result<Json<Value>, JsonError<'_>>,
+) -> Result<Json<pricing_maps::RiskMetricResponse>, constraints::ParameterError> {
+    let parameters: constraints::OptionParameters =
+        constraints::parse_model_parameters(model, parameters?.into_inner())?;
     constraints::check_parameters(&parameters, &constraints::PARAMETER_CONSTRAINTS)?;
 
     let constraints::OptionParameters {
         maturity,
-        rate,
+        rates,
+        asset,
         num_u: num_u_base,
         quantile,
+        truncation_scale,
         cf_parameters,
         ..
-    } = parameters.into_inner(); //destructure
+    } = parameters; //destructure
 
-    let num_u = (2 as usize).pow(num_u_base as u32);
+    let (pricing, effective_rate) = pricing_maps::get_pricing_parameters(
+        &rates,
+        asset,
+        maturity,
+        2_usize.pow(num_u_base.exponent()? as u32),
+        truncation_scale.unwrap_or(DENSITY_SCALE),
+    )?;
     let quantile_unwrap = quantile.ok_or(constraints::throw_no_exist_error("quantile"))?;
     let results = task::spawn_blocking(move || {
-        pricing_maps::get_risk_measure_results_as_json(
+        pricing_maps::get_risk_measure_results_as_json(&cf_parameters, &pricing, quantile_unwrap)
+    })
+    .await??;
+
+    Ok(Json(pricing_maps::RiskMetricResponse {
+        risk_metric: results,
+        effective_rate,
+    }))
+}
+
+#[post("/<model>/cdf", data = "<parameters>")]
+pub async fn cdf(
+    model: &str,
+    parameters: Result<Json<Value>, JsonError<'_>>,
+) -> Result<Json<Vec<pricing_maps::DistributionElement>>, constraints::ParameterError> {
+    let parameters: constraints::OptionParameters =
+        constraints::parse_model_parameters(model, parameters?.into_inner())?;
+    constraints::check_parameters(&parameters, &constraints::PARAMETER_CONSTRAINTS)?;
+
+    let constraints::OptionParameters {
+        maturity,
+        rates,
+        asset,
+        num_u: num_u_base,
+        truncation_scale,
+        log_returns,
+        prices,
+        cf_parameters,
+        ..
+    } = parameters; //destructure
+
+    let points = pricing_maps::get_log_returns(log_returns.as_deref(), prices.as_deref(), asset)?;
+    let (pricing, _) = pricing_maps::get_pricing_parameters(
+        &rates,
+        asset,
+        maturity,
+        2_usize.pow(num_u_base.exponent()? as u32),
+        truncation_scale.unwrap_or(DENSITY_SCALE),
+    )?;
+    let results = task::spawn_blocking(move || {
+        pricing_maps::get_cdf_results_as_json(&cf_parameters, &pricing, asset, &points)
+    })
+    .await??;
+
+    Ok(Json(results))
+}
+
+#[post("/<model>/quantile", data = "<parameters>")]
+pub async fn quantile(
+    model: &str,
+    parameters: Result<Json<Value>, JsonError<'_>>,
+) -> Result<Json<Vec<pricing_maps::DistributionElement>>, constraints::ParameterError> {
+    let parameters: constraints::OptionParameters =
+        constraints::parse_model_parameters(model, parameters?.into_inner())?;
+    constraints::check_parameters(&parameters, &constraints::PARAMETER_CONSTRAINTS)?;
+
+    let constraints::OptionParameters {
+        maturity,
+        rates,
+        asset,
+        num_u: num_u_base,
+        truncation_scale,
+        probabilities,
+        cf_parameters,
+        ..
+    } = parameters; //destructure
+
+    let points = probabilities.ok_or(constraints::throw_no_exist_error("probabilities"))?;
+    let (pricing, _) = pricing_maps::get_pricing_parameters(
+        &rates,
+        asset,
+        maturity,
+        2_usize.pow(num_u_base.exponent()? as u32),
+        truncation_scale.unwrap_or(DENSITY_SCALE),
+    )?;
+    let results = task::spawn_blocking(move || {
+        pricing_maps::get_quantile_results_as_json(&cf_parameters, &pricing, asset, &points)
+    })
+    .await??;
+
+    Ok(Json(results))
+}
+
+#[post("/<model>/surface?<check_arbitrage>", data = "<parameters>")]
+pub async fn surface(
+    model: &str,
+    parameters: Result<Json<Value>, JsonError<'_>>,
+    check_arbitrage: Option<bool>,
+) -> Result<Json<pricing_maps::SurfaceResponse>, constraints::ParameterError> {
+    let parameters: constraints::SurfaceParameters =
+        constraints::parse_model_parameters(model, parameters?.into_inner())?;
+    constraints::check_surface_parameters(&parameters, &constraints::PARAMETER_CONSTRAINTS)?;
+
+    let num_u = (2 as usize).pow(parameters.num_u as u32);
+    let option_scale = parameters.truncation_scale.unwrap_or(OPTION_SCALE);
+    let check_arbitrage = check_arbitrage.unwrap_or(false);
+    let results = task::spawn_blocking(move || {
+        pricing_maps::get_surface_results_as_json(&parameters, option_scale, num_u, check_arbitrage)
+    })
+    .await??;
+
+    Ok(Json(results))
+}
+
+#[post("/<model>/calibrator", data = "<parameters>")]
+pub async fn calibrator(
+    model: &str,
+    parameters: Result<Json<constraints::CalibrationParameters>, JsonError<'_>>,
+) -> Result<Json<constraints::CalibrationResponse>, constraints::ParameterError> {
+    let parameters = parameters?;
+    constraints::check_calibration_parameters(&parameters, &constraints::PARAMETER_CONSTRAINTS)?;
+
+    let parameters = parameters.into_inner();
+    let market = calibration::CalibrationMarket::new(&parameters, OPTION_SCALE);
+    let objective = parameters
+        .objective
+        .unwrap_or(constraints::CalibrationObjective::Price);
+    let option_data = parameters.option_data;
+    let cf_parameters = constraints::CFParameters::from_model(model)?;
+    let results = task::spawn_blocking(move || {
+        calibration::get_calibration_results_as_json(
             &cf_parameters,
-            DENSITY_SCALE,
-            num_u,
-            maturity,
-            rate,
-            quantile_unwrap,
+            objective,
+            &market,
+            &option_data,
+            &calibration::OptimizationOptions::default(),
+            &calibration::OptimizationProgress::default(),
         )
     })
     .await??;
@@ -126,11 +422,103 @@ pub async fn risk_metric(
     Ok(Json(results))
 }
 
+#[post("/<model>/calibrator/jobs", data = "<parameters>")]
+pub async fn calibrator_job(
+    model: &str,
+    parameters: Result<Json<constraints::CalibrationParameters>, JsonError<'_>>,
+    job_store: &State<jobs::JobStore>,
+) -> Result<status::Accepted<Json<jobs::JobResponse>>, constraints::ParameterError> {
+    let parameters = parameters?;
+    constraints::check_calibration_parameters(&parameters, &constraints::PARAMETER_CONSTRAINTS)?;
+    let cf_parameters = constraints::CFParameters::from_model(model)?; //fail before submitting
+
+    let parameters = parameters.into_inner();
+    let market = calibration::CalibrationMarket::new(&parameters, OPTION_SCALE);
+    let objective = parameters
+        .objective
+        .unwrap_or(constraints::CalibrationObjective::Price);
+    let option_data = parameters.option_data;
+    let options = calibration::OptimizationOptions::default();
+    let (id, progress) = job_store.submit(
+        model,
+        options.max_global_iterations + options.max_local_iterations,
+    );
+    let store = job_store.inner().clone();
+    let job_id = id.clone();
+    //not awaited: the job reports back to the store
+    task::spawn_blocking(move || {
+        let results = calibration::get_calibration_results_as_json(
+            &cf_parameters,
+            objective,
+            &market,
+            &option_data,
+            &options,
+            &progress,
+        );
+        store.finish(&job_id, results.map(|results| json!(results)));
+    });
+
+    Ok(status::Accepted(Json(job_store.get_status(model, &id)?)))
+}
+
+#[get("/<model>/calibrator/jobs/<id>")]
+pub async fn calibrator_job_status(
+    model: &str,
+    id: &str,
+    job_store: &State<jobs::JobStore>,
+) -> Result<Json<jobs::JobResponse>, constraints::ParameterError> {
+    Ok(Json(job_store.get_status(model, id)?))
+}
+
+#[get("/<model>/calibrator/jobs/<id>/progress")]
+pub async fn calibrator_job_progress(
+    model: &str,
+    id: &str,
+    job_store: &State<jobs::JobStore>,
+) -> Result<Json<jobs::JobProgress>, constraints::ParameterError> {
+    Ok(Json(job_store.get_progress(model, id)?))
+}
+
+#[get("/<model>/calibrator/jobs/<id>/result")]
+pub async fn calibrator_job_result(
+    model: &str,
+    id: &str,
+    job_store: &State<jobs::JobStore>,
+) -> Result<Value, constraints::ParameterError> {
+    job_store.get_result(model, id)
+}
+
+#[delete("/<model>/calibrator/jobs/<id>")]
+pub async fn calibrator_job_cancel(
+    model: &str,
+    id: &str,
+    job_store: &State<jobs::JobStore>,
+) -> Result<Json<jobs::JobResponse>, constraints::ParameterError> {
+    Ok(Json(job_store.cancel(model, id)?))
+}
+
 #[launch]
 fn rocket() -> _ {
     let mount_point = env::var("MAJOR_VERSION").unwrap();
-    rocket::build().mount(
-        format!("/{}", mount_point.as_str()).as_str(),
-        routes![parameters, calculator, density, risk_metric],
-    )
+    rocket::build()
+        .manage(jobs::JobStore::new(Duration::from_secs(JOB_TTL_SECONDS)))
+        .mount(
+            format!("/{}", mount_point.as_str()).as_str(),
+            routes![
+                parameters,
+                calculator,
+                greeks,
+                density,
+                risk_metric,
+                cdf,
+                quantile,
+                surface,
+                calibrator,
+                calibrator_job,
+                calibrator_job_status,
+                calibrator_job_progress,
+                calibrator_job_result,
+                calibrator_job_cancel
+            ],
+        )
 }
@@ -0,0 +1,285 @@
+use serde_derive::{Deserialize, Serialize};
+
+/// Relative tolerance, scaled by the discounted forward, below
+/// which violations are treated as numerical noise
+const TOLERANCE: f64 = 0.0000001;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArbitrageCondition {
+    Bounds,
+    CallSpread,
+    Butterfly,
+    Calendar,
+    PutCallParity,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArbitrageViolation {
+    pub condition: ArbitrageCondition,
+    pub strikes: Vec<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")] //only for calendar
+    pub maturities: Option<Vec<f64>>,
+    pub size: f64,
+}
+
+fn sorted_indices(values: &[f64]) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..values.len()).collect();
+    indices.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+    indices
+}
+
+fn strike_violation(
+    condition: ArbitrageCondition,
+    strikes: Vec<f64>,
+    size: f64,
+) -> ArbitrageViolation {
+    ArbitrageViolation {
+        condition,
+        strikes,
+        maturities: None,
+        size,
+    }
+}
+
+/// Checks call prices at a single maturity for bounds, call
+/// spread and butterfly violations.  The discounted forward is
+/// the dividend adjusted asset.
+pub fn get_strike_violations(
+    strikes: &[f64],
+    prices: &[f64],
+    forward: f64,
+    discount: f64,
+) -> Vec<ArbitrageViolation> {
+    let tolerance = TOLERANCE * forward * discount;
+    let indices = sorted_indices(strikes);
+    let mut violations = vec![];
+    for &i in indices.iter() {
+        let lower = (discount * (forward - strikes[i])).max(0.0);
+        let upper = discount * forward;
+        let size = (lower - prices[i]).max(prices[i] - upper);
+        if size > tolerance {
+            violations.push(strike_violation(
+                ArbitrageCondition::Bounds,
+                vec![strikes[i]],
+                size,
+            ));
+        }
+    }
+    for pair in indices.windows(2) {
+        let (k1, k2) = (strikes[pair[0]], strikes[pair[1]]);
+        let spread = prices[pair[0]] - prices[pair[1]];
+        //calls decrease in strike, but no faster than the discounted strike
+        let size = (-spread).max(spread - discount * (k2 - k1));
+        if size > tolerance {
+            violations.push(strike_violation(
+                ArbitrageCondition::CallSpread,
+                vec![k1, k2],
+                size,
+            ));
+        }
+    }
+    for triple in indices.windows(3) {
+        let (k1, k2, k3) = (strikes[triple[0]], strikes[triple[1]], strikes[triple[2]]);
+        if k3 <= k1 {
+            continue;
+        }
+        let weight = (k3 - k2) / (k3 - k1);
+        let size =
+            prices[triple[1]] - weight * prices[triple[0]] - (1.0 - weight) * prices[triple[2]];
+        if size > tolerance {
+            violations.push(strike_violation(
+                ArbitrageCondition::Butterfly,
+                vec![k1, k2, k3],
+                size,
+            ));
+        }
+    }
+    violations
+}
+
+/// Checks that calls less puts equal the discounted forward
+/// less the discounted strike, for prices at the same strikes
+pub fn get_put_call_parity_violations(
+    strikes: &[f64],
+    calls: &[f64],
+    puts: &[f64],
+    forward: f64,
+    discount: f64,
+) -> Vec<ArbitrageViolation> {
+    let tolerance = TOLERANCE * forward * discount;
+    sorted_indices(strikes)
+        .into_iter()
+        .filter_map(|i| {
+            let size = (calls[i] - puts[i] - discount * (forward - strikes[i])).abs();
+            if size > tolerance {
+                Some(strike_violation(
+                    ArbitrageCondition::PutCallParity,
+                    vec![strikes[i]],
+                    size,
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Linearly interpolates sorted x, y pairs, returning None
+/// outside of the range of x
+fn interpolate(x: &[f64], y: &[f64], at: f64) -> Option<f64> {
+    let upper = x.iter().position(|v| *v >= at)?;
+    if upper == 0 {
+        return if x[0] == at { Some(y[0]) } else { None };
+    }
+    let weight = (at - x[upper - 1]) / (x[upper] - x[upper - 1]);
+    Some(y[upper - 1] + weight * (y[upper] - y[upper - 1]))
+}
+
+/// Checks that call prices, normalized by the discounted
+/// forward, increase with maturity at fixed forward moneyness.
+/// Each maturity is compared against the next longer one,
+/// interpolated in moneyness; strikes outside of the longer
+/// maturity's range are skipped.
+pub fn get_calendar_violations(
+    maturities: &[f64],
+    strikes: &[Vec<f64>],
+    prices: &[Vec<f64>],
+    forwards: &[f64],
+    discounts: &[f64],
+) -> Vec<ArbitrageViolation> {
+    let normalized: Vec<(Vec<f64>, Vec<f64>)> = strikes
+        .iter()
+        .zip(prices.iter())
+        .enumerate()
+        .map(|(index, (slice_strikes, slice_prices))| {
+            sorted_indices(slice_strikes)
+                .into_iter()
+                .map(|i| {
+                    (
+                        slice_strikes[i] / forwards[index],
+                        slice_prices[i] / (discounts[index] * forwards[index]),
+                    )
+                })
+                .unzip()
+        })
+        .collect();
+    let mut violations = vec![];
+    for pair in sorted_indices(maturities).windows(2) {
+        let (short, long) = (pair[0], pair[1]);
+        if maturities[long] <= maturities[short] {
+            continue;
+        }
+        let (short_moneyness, short_prices) = &normalized[short];
+        let (long_moneyness, long_prices) = &normalized[long];
+        let scale = discounts[long] * forwards[long];
+        for (moneyness, price) in short_moneyness.iter().zip(short_prices.iter()) {
+            if let Some(long_price) = interpolate(long_moneyness, long_prices, *moneyness) {
+                let size = (price - long_price) * scale;
+                if size > TOLERANCE * scale {
+                    violations.push(ArbitrageViolation {
+                        condition: ArbitrageCondition::Calendar,
+                        strikes: vec![moneyness * forwards[short], moneyness * forwards[long]],
+                        maturities: Some(vec![maturities[short], maturities[long]]),
+                        size,
+                    });
+                }
+            }
+        }
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::*;
+    #[test]
+    fn black_scholes_prices_have_no_violations() {
+        let strikes = vec![70.0, 85.0, 100.0, 115.0, 130.0];
+        let maturities = vec![0.5, 1.0];
+        let rate = 0.05;
+        let prices: Vec<Vec<f64>> = maturities
+            .iter()
+            .map(|maturity| {
+                strikes
+                    .iter()
+                    .map(|strike| black_scholes::call(100.0, *strike, rate, 0.3, *maturity))
+                    .collect()
+            })
+            .collect();
+        let forwards: Vec<f64> = maturities
+            .iter()
+            .map(|maturity| 100.0 * (rate * maturity).exp())
+            .collect();
+        let discounts: Vec<f64> = maturities
+            .iter()
+            .map(|maturity| (-rate * maturity).exp())
+            .collect();
+        for i in 0..maturities.len() {
+            let violations = get_strike_violations(&strikes, &prices[i], forwards[i], discounts[i]);
+            assert!(violations.is_empty());
+        }
+        let all_strikes = vec![strikes.clone(), strikes];
+        let violations =
+            get_calendar_violations(&maturities, &all_strikes, &prices, &forwards, &discounts);
+        assert!(violations.is_empty());
+    }
+    #[test]
+    fn reports_each_strike_condition() {
+        let strikes = vec![120.0, 90.0, 100.0, 110.0];
+        let prices = vec![1.0, 5.0, 12.0, 14.0];
+        let violations = get_strike_violations(&strikes, &prices, 100.0, 1.0);
+        assert!(violations.contains(&ArbitrageViolation {
+            condition: ArbitrageCondition::Bounds,
+            strikes: vec![90.0],
+            maturities: None,
+            size: 5.0,
+        }));
+        assert!(violations.contains(&ArbitrageViolation {
+            condition: ArbitrageCondition::CallSpread,
+            strikes: vec![100.0, 110.0],
+            maturities: None,
+            size: 2.0,
+        }));
+        assert!(violations.contains(&ArbitrageViolation {
+            condition: ArbitrageCondition::Butterfly,
+            strikes: vec![100.0, 110.0, 120.0],
+            maturities: None,
+            size: 7.5,
+        }));
+    }
+    #[test]
+    fn reports_put_call_parity_violation() {
+        let strikes = vec![110.0, 90.0, 100.0];
+        let calls = vec![2.0, 12.0, 6.0];
+        let puts = vec![12.0, 2.0, 7.0];
+        let violations = get_put_call_parity_violations(&strikes, &calls, &puts, 100.0, 1.0);
+        assert_eq!(
+            violations,
+            vec![ArbitrageViolation {
+                condition: ArbitrageCondition::PutCallParity,
+                strikes: vec![100.0],
+                maturities: None,
+                size: 1.0,
+            }]
+        );
+    }
+    #[test]
+    fn sorts_strikes_with_nan() {
+        let indices = sorted_indices(&[2.0, f64::NAN, 1.0]);
+        assert_eq!(indices, vec![2, 0, 1]);
+    }
+    #[test]
+    fn reports_calendar_violation() {
+        let strikes = vec![vec![90.0, 100.0, 110.0], vec![90.0, 100.0, 110.0]];
+        let prices = vec![vec![12.0, 6.0, 2.0], vec![12.0, 5.0, 2.0]];
+        let violations =
+            get_calendar_violations(&[0.5, 1.0], &strikes, &prices, &[100.0, 100.0], &[1.0, 1.0]);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].condition, ArbitrageCondition::Calendar);
+        assert_eq!(violations[0].strikes, vec![100.0, 100.0]);
+        assert_eq!(violations[0].maturities, Some(vec![0.5, 1.0]));
+        assert_abs_diff_eq!(violations[0].size, 1.0, epsilon = 0.0000001);
+    }
+}
@@ -0,0 +1,1611 @@
+use crate::constraints::{
+    throw_no_convergence_error, throw_no_exist_error, ActiveBound, CFParameters,
+    CalibrationDiagnostics, CalibrationObjective, CalibrationParameters, CalibrationResponse,
+    ErrorType, FittedQuote, FittedQuoteMaturity, OptionQuote, OptionQuoteMaturity, ParameterError,
+    ParameterUncertainty,
+};
+use crate::pricing_maps::{
+    get_option_results_as_json, GraphElement, ImpliedVolatilityMode, OptionKind, PricingParameters,
+    Sensitivity,
+};
+use rand::{distributions::Distribution, distributions::Uniform, rngs::StdRng, SeedableRng};
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+/// Box constraint for a single parameter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bound {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Bound {
+    fn clamp(&self, value: f64) -> f64 {
+        value.max(self.lower).min(self.upper)
+    }
+    fn width(&self) -> f64 {
+        self.upper - self.lower
+    }
+}
+
+impl From<(f64, f64)> for Bound {
+    fn from((lower, upper): (f64, f64)) -> Bound {
+        Bound { lower, upper }
+    }
+}
+
+/// Settings for the global search and the local polish
+pub struct OptimizationOptions {
+    /// Number of candidates per parameter in the differential evolution population
+    pub population_per_parameter: usize,
+    /// Maximum number of differential evolution generations
+    pub max_global_iterations: usize,
+    /// Maximum number of Nelder-Mead iterations
+    pub max_local_iterations: usize,
+    /// Differential weight ("F")
+    pub mutation: f64,
+    /// Crossover probability ("CR")
+    pub crossover: f64,
+    /// Stop when the spread of costs falls below this value
+    pub tolerance: f64,
+    pub seed: u64,
+}
+
+impl Default for OptimizationOptions {
+    fn default() -> Self {
+        OptimizationOptions {
+            population_per_parameter: 10,
+            max_global_iterations: 100,
+            max_local_iterations: 1000,
+            mutation: 0.7,
+            crossover: 0.9,
+            tolerance: 0.00000001,
+            seed: 42,
+        }
+    }
+}
+
+pub struct OptimizationResult {
+    pub parameters: Vec<f64>,
+    pub cost: f64,
+    pub global_iterations: usize,
+    pub local_iterations: usize,
+    /// Best cost after every iteration, global search first
+    pub cost_history: Vec<f64>,
+}
+
+/// Shared between a running optimization and anyone
+/// watching it: reports iterations and the best cost so
+/// far, and stops the optimization once cancelled.
+pub struct OptimizationProgress {
+    cancelled: AtomicBool,
+    iterations: AtomicUsize,
+    best_cost: AtomicU64, //f64 bits
+}
+
+impl Default for OptimizationProgress {
+    fn default() -> Self {
+        OptimizationProgress {
+            cancelled: AtomicBool::new(false),
+            iterations: AtomicUsize::new(0),
+            best_cost: AtomicU64::new(f64::NAN.to_bits()),
+        }
+    }
+}
+
+impl OptimizationProgress {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+    /// Completed iterations, global and local combined
+    pub fn iterations(&self) -> usize {
+        self.iterations.load(Ordering::Relaxed)
+    }
+    /// NaN until the first iteration completes
+    pub fn best_cost(&self) -> f64 {
+        f64::from_bits(self.best_cost.load(Ordering::Relaxed))
+    }
+    fn record(&self, cost_history: &mut Vec<f64>, cost: f64) {
+        cost_history.push(cost);
+        self.iterations.fetch_add(1, Ordering::Relaxed);
+        self.best_cost.store(cost.to_bits(), Ordering::Relaxed);
+    }
+}
+
+fn clamp_to_bounds(params: &mut [f64], bounds: &[Bound]) {
+    params
+        .iter_mut()
+        .zip(bounds)
+        .for_each(|(param, bound)| *param = bound.clamp(*param));
+}
+
+fn sort_by_cost(points: &mut [(Vec<f64>, f64)]) {
+    points.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+}
+
+fn get_best(points: &[(Vec<f64>, f64)]) -> &(Vec<f64>, f64) {
+    points
+        .iter()
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .unwrap()
+}
+
+/// Differential evolution (rand/1/bin) over the box
+/// defined by `bounds`.  Returns the best point, its cost,
+/// and the number of generations.
+fn differential_evolution<T>(
+    obj_fn: &T,
+    bounds: &[Bound],
+    options: &OptimizationOptions,
+    progress: &OptimizationProgress,
+    cost_history: &mut Vec<f64>,
+) -> (Vec<f64>, f64, usize)
+where
+    T: Fn(&[f64]) -> f64 + Sync,
+{
+    let n = bounds.len();
+    let population_size = (options.population_per_parameter * n).max(4);
+    let mut rng: StdRng = SeedableRng::seed_from_u64(options.seed);
+    let uniform = Uniform::new(0.0f64, 1.0);
+    let mut population: Vec<(Vec<f64>, f64)> = (0..population_size)
+        .map(|_| {
+            bounds
+                .iter()
+                .map(|bound| bound.lower + bound.width() * uniform.sample(&mut rng))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|point| {
+            let cost = obj_fn(&point);
+            (point, cost)
+        })
+        .collect();
+    let mut iterations = 0;
+    while iterations < options.max_global_iterations && !progress.is_cancelled() {
+        let (_, best_cost) = get_best(&population);
+        let worst_cost = population
+            .iter()
+            .map(|(_, cost)| *cost)
+            .fold(f64::MIN, f64::max);
+        if worst_cost - best_cost < options.tolerance {
+            break;
+        }
+        iterations += 1;
+        let trials: Vec<Vec<f64>> = (0..population_size)
+            .map(|index| {
+                let mut chosen = vec![index];
+                let mut pick = || loop {
+                    let candidate = ((uniform.sample(&mut rng) * population_size as f64) as usize)
+                        .min(population_size - 1);
+                    if !chosen.contains(&candidate) {
+                        chosen.push(candidate);
+                        return candidate;
+                    }
+                };
+                let (a, b, c) = (pick(), pick(), pick());
+                let forced = (uniform.sample(&mut rng) * n as f64) as usize;
+                let mut trial: Vec<f64> = (0..n)
+                    .map(|j| {
+                        if j == forced || uniform.sample(&mut rng) < options.crossover {
+                            population[a].0[j]
+                                + options.mutation * (population[b].0[j] - population[c].0[j])
+                        } else {
+                            population[index].0[j]
+                        }
+                    })
+                    .collect();
+                clamp_to_bounds(&mut trial, bounds);
+                trial
+            })
+            .collect();
+        let trial_costs: Vec<f64> = trials.par_iter().map(|trial| obj_fn(trial)).collect();
+        population
+            .iter_mut()
+            .zip(trials.into_iter().zip(trial_costs))
+            .for_each(|(current, (trial, trial_cost))| {
+                if trial_cost <= current.1 {
+                    *current = (trial, trial_cost);
+                }
+            });
+        progress.record(cost_history, get_best(&population).1);
+    }
+    let (best, best_cost) = get_best(&population).clone();
+    (best, best_cost, iterations)
+}
+
+/// Nelder-Mead minimization starting from `initial`.
+/// Each candidate point is projected back onto the
+/// bounds.  Returns the best point, its cost, and the
+/// number of iterations.
+fn nelder_mead<T>(
+    obj_fn: &T,
+    initial: &[f64],
+    bounds: &[Bound],
+    options: &OptimizationOptions,
+    progress: &OptimizationProgress,
+    cost_history: &mut Vec<f64>,
+) -> (Vec<f64>, f64, usize)
+where
+    T: Fn(&[f64]) -> f64,
+{
+    const INITIAL_SIMPLEX_SCALE: f64 = 0.05;
+    let n = bounds.len();
+    let mut simplex: Vec<(Vec<f64>, f64)> = (0..=n)
+        .map(|index| {
+            let mut point = initial.to_vec();
+            if index > 0 {
+                let bound = bounds[index - 1];
+                let step = INITIAL_SIMPLEX_SCALE * bound.width();
+                //step away from the bound if already sitting on it
+                point[index - 1] += if point[index - 1] + step > bound.upper {
+                    -step
+                } else {
+                    step
+                };
+                clamp_to_bounds(&mut point, bounds);
+            }
+            let cost = obj_fn(&point);
+            (point, cost)
+        })
+        .collect();
+    let to_point = |centroid: &[f64], worst: &[f64], coefficient: f64| -> Vec<f64> {
+        let mut point: Vec<f64> = centroid
+            .iter()
+            .zip(worst)
+            .map(|(c, w)| c + coefficient * (w - c))
+            .collect();
+        clamp_to_bounds(&mut point, bounds);
+        point
+    };
+    let mut iterations = 0;
+    while iterations < options.max_local_iterations && !progress.is_cancelled() {
+        sort_by_cost(&mut simplex);
+        if simplex[n].1 - simplex[0].1 < options.tolerance {
+            break;
+        }
+        iterations += 1;
+        let centroid: Vec<f64> = (0..n)
+            .map(|index| {
+                simplex[..n]
+                    .iter()
+                    .map(|(point, _)| point[index])
+                    .sum::<f64>()
+                    / n as f64
+            })
+            .collect();
+        let reflected = to_point(&centroid, &simplex[n].0, -1.0);
+        let reflected_cost = obj_fn(&reflected);
+        if reflected_cost < simplex[0].1 {
+            let expanded = to_point(&centroid, &simplex[n].0, -2.0);
+            let expanded_cost = obj_fn(&expanded);
+            simplex[n] = if expanded_cost < reflected_cost {
+                (expanded, expanded_cost)
+            } else {
+                (reflected, reflected_cost)
+            };
+        } else if reflected_cost < simplex[n - 1].1 {
+            simplex[n] = (reflected, reflected_cost);
+        } else {
+            let contracted = to_point(&centroid, &simplex[n].0, 0.5);
+            let contracted_cost = obj_fn(&contracted);
+            if contracted_cost < simplex[n].1 {
+                simplex[n] = (contracted, contracted_cost);
+            } else {
+                let best = simplex[0].0.clone();
+                simplex.iter_mut().skip(1).for_each(|(point, cost)| {
+                    *point = to_point(&best, point, 0.5);
+                    *cost = obj_fn(point);
+                });
+            }
+        }
+        progress.record(cost_history, get_best(&simplex).1);
+    }
+    let (best, best_cost) = get_best(&simplex).clone();
+    (best, best_cost, iterations)
+}
+
+/// Minimizes `obj_fn` inside the box defined by `bounds`
+/// using differential evolution followed by a Nelder-Mead
+/// polish of the best candidate.
+/// # Examples
+///
+/// ```
+/// extern crate utils;
+/// use utils::calibration;
+/// # fn main() {
+/// let bounds = vec![
+///     calibration::Bound { lower: -1.0, upper: 1.0 },
+///     calibration::Bound { lower: 0.5, upper: 2.0 },
+/// ];
+/// let result = calibration::minimize(
+///     |x: &[f64]| x[0].powi(2) + x[1].powi(2),
+///     &bounds,
+///     &calibration::OptimizationOptions::default()
+/// );
+/// assert!((result.parameters[1] - 0.5).abs() < 0.0001);
+/// # }
+/// ```
+pub fn minimize<T>(obj_fn: T, bounds: &[Bound], options: &OptimizationOptions) -> OptimizationResult
+where
+    T: Fn(&[f64]) -> f64 + Sync,
+{
+    minimize_with_progress(obj_fn, bounds, options, &OptimizationProgress::default())
+}
+
+/// Same as `minimize`, but reports to `progress` after
+/// every iteration and stops early (returning the best
+/// point so far) once `progress` is cancelled.
+pub fn minimize_with_progress<T>(
+    obj_fn: T,
+    bounds: &[Bound],
+    options: &OptimizationOptions,
+    progress: &OptimizationProgress,
+) -> OptimizationResult
+where
+    T: Fn(&[f64]) -> f64 + Sync,
+{
+    //skip the remaining evaluations of a generation once cancelled
+    let obj_fn = |params: &[f64]| {
+        if progress.is_cancelled() {
+            f64::INFINITY
+        } else {
+            obj_fn(params)
+        }
+    };
+    let mut cost_history = vec![];
+    let (global_best, _, global_iterations) =
+        differential_evolution(&obj_fn, bounds, options, progress, &mut cost_history);
+    let (parameters, cost, local_iterations) = nelder_mead(
+        &obj_fn,
+        &global_best,
+        bounds,
+        options,
+        progress,
+        &mut cost_history,
+    );
+    OptimizationResult {
+        parameters,
+        cost,
+        global_iterations,
+        local_iterations,
+        cost_history,
+    }
+}
+
+/// Returns the parameters sitting on (or numerically
+/// next to) one of their bounds
+pub fn get_active_bounds(parameters: &[(f64, &str)], bounds: &[Bound]) -> Vec<ActiveBound> {
+    const BOUND_TOLERANCE: f64 = 0.000001;
+    parameters
+        .iter()
+        .zip(bounds)
+        .filter_map(|((value, name), bound)| {
+            let tolerance = BOUND_TOLERANCE * bound.width();
+            if (value - bound.lower).abs() <= tolerance {
+                Some(ActiveBound {
+                    parameter: name.to_string(),
+                    bound: "lower".to_string(),
+                })
+            } else if (bound.upper - value).abs() <= tolerance {
+                Some(ActiveBound {
+                    parameter: name.to_string(),
+                    bound: "upper".to_string(),
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Returns the forward, backward, or central finite
+/// difference jacobian of the residuals (one row per
+/// residual, one column per parameter).  Parameters
+/// sitting on a bound are only bumped into the interior.
+pub fn get_jacobian<T>(residual_fn: T, parameters: &[f64], bounds: &[Bound]) -> Vec<Vec<f64>>
+where
+    T: Fn(&[f64]) -> Vec<f64> + Sync,
+{
+    const RELATIVE_STEP: f64 = 0.0001;
+    let columns: Vec<Vec<f64>> = parameters
+        .par_iter()
+        .zip(bounds)
+        .enumerate()
+        .map(|(index, (value, bound))| {
+            let step = RELATIVE_STEP * bound.width();
+            let upper = (value + step).min(bound.upper);
+            let lower = (value - step).max(bound.lower);
+            let bump = |v: f64| {
+                let mut bumped = parameters.to_vec();
+                bumped[index] = v;
+                residual_fn(&bumped)
+            };
+            let upper_residuals = bump(upper);
+            let lower_residuals = bump(lower);
+            upper_residuals
+                .iter()
+                .zip(&lower_residuals)
+                .map(|(u, l)| (u - l) / (upper - lower))
+                .collect()
+        })
+        .collect();
+    let num_residuals = columns.first().map_or(0, |column| column.len());
+    (0..num_residuals)
+        .map(|row| columns.iter().map(|column| column[row]).collect())
+        .collect()
+}
+
+/// Cyclic Jacobi eigen decomposition of a symmetric
+/// matrix.  Returns the eigenvalues and the matrix
+/// whose columns are the corresponding eigenvectors.
+fn symmetric_eigen(matrix: &[Vec<f64>]) -> (Vec<f64>, Vec<Vec<f64>>) {
+    const MAX_SWEEPS: usize = 100;
+    let n = matrix.len();
+    let mut a = matrix.to_vec();
+    let mut v: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+    let norm: f64 = a.iter().flatten().map(|x| x.powi(2)).sum();
+    for _ in 0..MAX_SWEEPS {
+        let off_diagonal: f64 = (0..n)
+            .flat_map(|i| (0..n).filter(move |j| *j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j].powi(2))
+            .sum();
+        if off_diagonal <= f64::EPSILON.powi(2) * norm {
+            break;
+        }
+        for p in 0..n {
+            for q in (p + 1)..n {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta.powi(2) + 1.0).sqrt());
+                let c = 1.0 / (t.powi(2) + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                let (row_p, row_q) = (a[p].clone(), a[q].clone());
+                a[p] = row_p
+                    .iter()
+                    .zip(&row_q)
+                    .map(|(apk, aqk)| c * apk - s * aqk)
+                    .collect();
+                a[q] = row_p
+                    .iter()
+                    .zip(&row_q)
+                    .map(|(apk, aqk)| s * apk + c * aqk)
+                    .collect();
+                for row in v.iter_mut() {
+                    let (vkp, vkq) = (row[p], row[q]);
+                    row[p] = c * vkp - s * vkq;
+                    row[q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    ((0..n).map(|i| a[i][i]).collect(), v)
+}
+
+/// Returns the least squares covariance
+/// s^2 (J^T J)^-1, with s^2 the residual sum of
+/// squares over the degrees of freedom, along with the
+/// standard errors, correlations, and the condition
+/// number of J.  Entries that are not defined (a
+/// singular jacobian or no degrees of freedom) are NaN,
+/// which serialize as null.
+pub fn get_parameter_uncertainty(
+    jacobian: &[Vec<f64>],
+    residuals: &[f64],
+    parameter_names: &[&str],
+) -> ParameterUncertainty {
+    let num_parameters = parameter_names.len();
+    let num_residuals = residuals.len();
+    let information: Vec<Vec<f64>> = (0..num_parameters)
+        .map(|i| {
+            (0..num_parameters)
+                .map(|j| jacobian.iter().map(|row| row[i] * row[j]).sum())
+                .collect()
+        })
+        .collect();
+    let (eigenvalues, eigenvectors) = symmetric_eigen(&information);
+    let max_eigenvalue = eigenvalues.iter().cloned().fold(0.0, f64::max);
+    let min_eigenvalue = eigenvalues.iter().cloned().fold(f64::INFINITY, f64::min);
+    let is_singular = num_parameters == 0
+        || min_eigenvalue <= max_eigenvalue * f64::EPSILON * num_parameters as f64;
+    let condition_number = if is_singular {
+        f64::NAN
+    } else {
+        (max_eigenvalue / min_eigenvalue).sqrt()
+    };
+    let variance = if num_residuals > num_parameters {
+        residuals.iter().map(|r| r.powi(2)).sum::<f64>() / (num_residuals - num_parameters) as f64
+    } else {
+        f64::NAN
+    };
+    let covariance: Vec<Vec<f64>> = (0..num_parameters)
+        .map(|i| {
+            (0..num_parameters)
+                .map(|j| {
+                    if is_singular {
+                        f64::NAN
+                    } else {
+                        variance
+                            * eigenvalues
+                                .iter()
+                                .enumerate()
+                                .map(|(k, eigenvalue)| {
+                                    eigenvectors[i][k] * eigenvectors[j][k] / eigenvalue
+                                })
+                                .sum::<f64>()
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let standard_errors: Vec<f64> = (0..num_parameters)
+        .map(|i| covariance[i][i].sqrt())
+        .collect();
+    let correlation = (0..num_parameters)
+        .map(|i| {
+            (0..num_parameters)
+                .map(|j| covariance[i][j] / (standard_errors[i] * standard_errors[j]))
+                .collect()
+        })
+        .collect();
+    ParameterUncertainty {
+        parameter_names: parameter_names
+            .iter()
+            .map(|name| name.to_string())
+            .collect(),
+        standard_errors,
+        covariance,
+        correlation,
+        condition_number,
+    }
+}
+
+//large cost for parameters where the model price is not finite.
+//Don't use MAX since summing can overflow into Inf
+const LARGE_COST: f64 = 100000000.0;
+
+//vega is floored at this fraction of asset*sqrt(maturity)
+//so that deep out of the money quotes do not dominate
+const VEGA_FLOOR: f64 = 0.01;
+
+/// Market inputs shared by every quote of a calibration
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CalibrationMarket {
+    pub asset: f64,
+    pub rate: f64,
+    pub dividend_yield: f64, //includes the borrow rate
+    pub num_u: usize,        //number of discrete "u", not its exponent
+    pub option_scale: f64,
+}
+
+impl CalibrationMarket {
+    /// Resolves the market inputs of a calibration request.
+    /// The borrow rate is added to the dividend yield, and
+    /// `default_scale` applies without a truncation scale.
+    pub fn new(parameters: &CalibrationParameters, default_scale: f64) -> Self {
+        let CalibrationParameters {
+            asset,
+            num_u,
+            rate,
+            dividend_yield,
+            borrow_rate,
+            truncation_scale,
+            ..
+        } = *parameters;
+        CalibrationMarket {
+            asset,
+            rate,
+            dividend_yield: dividend_yield.unwrap_or(0.0) + borrow_rate.unwrap_or(0.0),
+            num_u: 2_usize.pow(num_u as u32),
+            option_scale: truncation_scale.unwrap_or(default_scale),
+        }
+    }
+    fn get_pricing_parameters(&self, maturity: f64) -> PricingParameters {
+        PricingParameters {
+            num_u: self.num_u,
+            scale: self.option_scale,
+            maturity,
+            rate: self.rate,
+            dividend_yield: self.dividend_yield,
+            cash_dividend_shift: 0.0,
+        }
+    }
+    /// Asset net of the dividend yield, so that the
+    /// Black-Scholes functions without a yield apply
+    fn get_adjusted_asset(&self, maturity: f64) -> f64 {
+        self.asset * (-self.dividend_yield * maturity).exp()
+    }
+}
+
+/// Market quote expressed as a call price, along with the
+/// Black-Scholes implied volatility and vega when the
+/// objective requires them
+struct MarketQuote {
+    price: f64,
+    bid: f64,
+    ask: f64,
+    mid: f64,
+    implied_volatility: f64,
+    vega: f64,
+    weight: f64,
+}
+
+struct MarketMaturity {
+    maturity: f64,
+    strikes: Vec<f64>,
+    quotes: Vec<MarketQuote>,
+}
+
+fn get_market_quote(
+    objective: CalibrationObjective,
+    market: &CalibrationMarket,
+    maturity: f64,
+    quote: &OptionQuote,
+) -> Result<MarketQuote, ParameterError> {
+    let asset = market.get_adjusted_asset(maturity);
+    let rate = market.rate;
+    let OptionQuote {
+        strike,
+        price,
+        implied_volatility,
+        bid,
+        ask,
+        weight,
+        open_interest,
+    } = quote;
+    let mid = match (bid, ask) {
+        (Some(bid), Some(ask)) if bid > ask => {
+            return Err(ParameterError::new(&ErrorType::OutOfBounds(
+                "bid".to_string(),
+            )))
+        }
+        (Some(bid), Some(ask)) => Some(0.5 * (bid + ask)),
+        _ => None,
+    };
+    let (price, implied_volatility) = match (price, implied_volatility, mid) {
+        (Some(price), iv, _) => (*price, *iv),
+        (None, Some(iv), _) => (
+            black_scholes::call(asset, *strike, rate, *iv, maturity),
+            Some(*iv),
+        ),
+        (None, None, Some(mid)) => (mid, None),
+        (None, None, None) => return Err(throw_no_exist_error("price")),
+    };
+    let needs_implied_volatility = match objective {
+        CalibrationObjective::VegaWeighted | CalibrationObjective::ImpliedVolatility => true,
+        CalibrationObjective::Price
+        | CalibrationObjective::RelativePrice
+        | CalibrationObjective::BidAsk => false,
+    };
+    if objective == CalibrationObjective::RelativePrice && price <= 0.0 {
+        return Err(ParameterError::new(&ErrorType::OutOfBounds(
+            "price".to_string(),
+        )));
+    }
+    let implied_volatility = match implied_volatility {
+        Some(iv) => iv,
+        None if needs_implied_volatility => {
+            black_scholes::call_iv(price, asset, *strike, rate, maturity)
+                .map_err(|_err| throw_no_convergence_error())?
+        }
+        None => f64::NAN,
+    };
+    let vega = black_scholes::call_vega(asset, *strike, rate, implied_volatility, maturity)
+        .max(VEGA_FLOOR * asset * maturity.sqrt());
+    let weight = weight.or(*open_interest).unwrap_or(1.0);
+    if weight < 0.0 {
+        return Err(ParameterError::new(&ErrorType::OutOfBounds(
+            "weight".to_string(),
+        )));
+    }
+    Ok(MarketQuote {
+        price,
+        //a missing side of the band collapses onto the price
+        bid: bid.unwrap_or(price),
+        ask: ask.unwrap_or(price),
+        mid: mid.unwrap_or(price),
+        implied_volatility,
+        vega,
+        weight,
+    })
+}
+
+fn get_market_maturities(
+    objective: CalibrationObjective,
+    market: &CalibrationMarket,
+    option_data: &[OptionQuoteMaturity],
+) -> Result<Vec<MarketMaturity>, ParameterError> {
+    option_data
+        .iter()
+        .map(
+            |OptionQuoteMaturity {
+                 maturity,
+                 option_data,
+             }| {
+                Ok(MarketMaturity {
+                    maturity: *maturity,
+                    strikes: option_data
+                        .iter()
+                        .map(|OptionQuote { strike, .. }| *strike)
+                        .collect(),
+                    quotes: option_data
+                        .iter()
+                        .map(|quote| get_market_quote(objective, market, *maturity, quote))
+                        .collect::<Result<Vec<_>, ParameterError>>()?,
+                })
+            },
+        )
+        .collect()
+}
+
+/// Returns the error between a model price and the
+/// market quote, in the units of the objective
+fn get_quote_error(
+    objective: CalibrationObjective,
+    model_price: f64,
+    quote: &MarketQuote,
+    market: &CalibrationMarket,
+    strike: f64,
+    maturity: f64,
+) -> f64 {
+    let price_error = model_price - quote.price;
+    match objective {
+        CalibrationObjective::Price => price_error,
+        CalibrationObjective::RelativePrice => price_error / quote.price,
+        CalibrationObjective::VegaWeighted => price_error / quote.vega,
+        //fall back to the vega approximation where
+        //the model price has no implied volatility
+        CalibrationObjective::ImpliedVolatility => {
+            let asset = market.get_adjusted_asset(maturity);
+            match black_scholes::call_iv(model_price, asset, strike, market.rate, maturity) {
+                Ok(iv) => iv - quote.implied_volatility,
+                Err(_) => price_error / quote.vega,
+            }
+        }
+        CalibrationObjective::BidAsk => {
+            (quote.bid - model_price).max(0.0) + (model_price - quote.ask).max(0.0)
+        }
+    }
+}
+
+/// Returns the quote errors scaled by the square root
+/// of their weights, so that the sum of squares is the
+/// weighted sum of squared errors
+fn get_weighted_residuals(
+    objective: CalibrationObjective,
+    cf_parameters: &CFParameters,
+    market: &CalibrationMarket,
+    market_data: &[MarketMaturity],
+) -> Vec<f64> {
+    let large_residual = LARGE_COST.sqrt();
+    market_data
+        .par_iter()
+        .flat_map(
+            |MarketMaturity {
+                 maturity,
+                 strikes,
+                 quotes,
+             }| {
+                match get_option_results_as_json(
+                    OptionKind::Call,
+                    Sensitivity::Price,
+                    ImpliedVolatilityMode::Skip,
+                    cf_parameters,
+                    &market.get_pricing_parameters(*maturity),
+                    market.asset,
+                    strikes,
+                ) {
+                    Ok(results) => results
+                        .iter()
+                        .zip(quotes)
+                        .map(
+                            |(
+                                GraphElement {
+                                    at_point, value, ..
+                                },
+                                quote,
+                            )| {
+                                let error = get_quote_error(
+                                    objective, *value, quote, market, *at_point, *maturity,
+                                );
+                                //check the price too since max(NaN, 0) is 0
+                                if value.is_finite() && error.is_finite() {
+                                    quote.weight.sqrt() * error
+                                } else {
+                                    quote.weight.sqrt() * large_residual
+                                }
+                            },
+                        )
+                        .collect::<Vec<_>>(),
+                    Err(_) => quotes
+                        .iter()
+                        .map(|quote| quote.weight.sqrt() * large_residual)
+                        .collect(),
+                }
+            },
+        )
+        .collect()
+}
+
+/// Returns the weighted root mean squared error
+/// between the market quotes and the model prices
+fn get_calibration_cost(weighted_residuals: &[f64], total_weight: f64) -> f64 {
+    let sum_square_error: f64 = weighted_residuals.iter().map(|r| r.powi(2)).sum();
+    (sum_square_error / total_weight).sqrt()
+}
+
+/// Returns the model price, market mid and residual
+/// for every quote at the calibrated parameters
+fn get_fitted_quotes(
+    cf_parameters: &CFParameters,
+    market: &CalibrationMarket,
+    market_data: &[MarketMaturity],
+) -> Result<Vec<FittedQuoteMaturity>, ParameterError> {
+    market_data
+        .iter()
+        .map(
+            |MarketMaturity {
+                 maturity,
+                 strikes,
+                 quotes,
+             }| {
+                let results = get_option_results_as_json(
+                    OptionKind::Call,
+                    Sensitivity::Price,
+                    ImpliedVolatilityMode::Skip,
+                    cf_parameters,
+                    &market.get_pricing_parameters(*maturity),
+                    market.asset,
+                    strikes,
+                )?;
+                Ok(FittedQuoteMaturity {
+                    maturity: *maturity,
+                    option_data: results
+                        .iter()
+                        .zip(quotes)
+                        .map(
+                            |(
+                                GraphElement {
+                                    at_point, value, ..
+                                },
+                                quote,
+                            )| FittedQuote {
+                                strike: *at_point,
+                                fitted_price: *value,
+                                market_mid: quote.mid,
+                                residual: value - quote.mid,
+                            },
+                        )
+                        .collect(),
+                })
+            },
+        )
+        .collect()
+}
+
+/// Returns model parameters fit to observed call
+/// prices (or implied volatilities) across maturities
+/// # Examples
+///
+/// ```
+/// extern crate utils;
+/// use utils::{calibration, constraints};
+/// use utils::constraints::{OptionQuote, OptionQuoteMaturity};
+/// # fn main() {
+/// let option_data = vec![OptionQuoteMaturity {
+///     maturity: 1.0,
+///     option_data: vec![
+///         OptionQuote { strike: 95.0, price: Some(9.0), ..OptionQuote::default() },
+///         OptionQuote { strike: 100.0, bid: Some(5.9), ask: Some(6.1), ..OptionQuote::default() },
+///         OptionQuote { strike: 105.0, implied_volatility: Some(0.14), ..OptionQuote::default() },
+///     ],
+/// }];
+/// let options = calibration::OptimizationOptions {
+///     max_global_iterations: 5,
+///     max_local_iterations: 50,
+///     ..calibration::OptimizationOptions::default()
+/// };
+/// let result = calibration::get_calibration_results_as_json(
+///     &constraints::CFParameters::from_model("heston").unwrap(),
+///     constraints::CalibrationObjective::ImpliedVolatility,
+///     &calibration::CalibrationMarket {
+///         asset: 100.0,
+///         rate: 0.0,
+///         dividend_yield: 0.0,
+///         num_u: 32,
+///         option_scale: 10.0,
+///     },
+///     &option_data,
+///     &options,
+///     &calibration::OptimizationProgress::default()
+/// ).unwrap();
+/// # }
+/// ```
+pub fn get_calibration_results_as_json(
+    model: &CFParameters, //only the model is used, see CFParameters::from_model
+    objective: CalibrationObjective,
+    market: &CalibrationMarket,
+    option_data: &[OptionQuoteMaturity],
+    options: &OptimizationOptions,
+    progress: &OptimizationProgress,
+) -> Result<CalibrationResponse, ParameterError> {
+    let bounds: Vec<Bound> = model.bounds().into_iter().map(Bound::from).collect();
+    if option_data
+        .iter()
+        .all(|OptionQuoteMaturity { option_data, .. }| option_data.is_empty())
+    {
+        return Err(throw_no_exist_error("option_data"));
+    }
+    let market_data = get_market_maturities(objective, market, option_data)?;
+    let total_weight: f64 = market_data
+        .iter()
+        .flat_map(|MarketMaturity { quotes, .. }| quotes.iter().map(|quote| quote.weight))
+        .sum();
+    if total_weight <= 0.0 {
+        return Err(ParameterError::new(&ErrorType::OutOfBounds(
+            "weight".to_string(),
+        )));
+    }
+    let get_residuals = |params: &[f64]| {
+        get_weighted_residuals(objective, &model.with_vector(params), market, &market_data)
+    };
+    let OptimizationResult {
+        parameters,
+        cost,
+        global_iterations,
+        local_iterations,
+        cost_history,
+    } = minimize_with_progress(
+        |params| get_calibration_cost(&get_residuals(params), total_weight),
+        &bounds,
+        options,
+        progress,
+    );
+    if progress.is_cancelled() {
+        return Err(ParameterError::new(&ErrorType::OptimizationError(
+            "Calibration was cancelled".to_string(),
+        )));
+    }
+    if !cost.is_finite() || cost >= LARGE_COST {
+        return Err(ParameterError::new(&ErrorType::OptimizationError(
+            "Calibration did not find parameters with finite option prices".to_string(),
+        )));
+    }
+    let cf_parameters = model.with_vector(&parameters);
+    let named_parameters = cf_parameters.to_vector();
+    let active_bounds = get_active_bounds(&named_parameters, &bounds);
+    let jacobian = get_jacobian(get_residuals, &parameters, &bounds);
+    let residuals = get_residuals(&parameters);
+    let parameter_names: Vec<&str> = named_parameters.iter().map(|(_, name)| *name).collect();
+    let uncertainty = get_parameter_uncertainty(&jacobian, &residuals, &parameter_names);
+    let fitted_quotes = get_fitted_quotes(&cf_parameters, market, &market_data)?;
+    Ok(CalibrationResponse {
+        parameters: cf_parameters,
+        final_cost_value: cost,
+        fitted_quotes,
+        diagnostics: CalibrationDiagnostics {
+            global_iterations,
+            local_iterations,
+            cost_history,
+            active_bounds,
+        },
+        uncertainty,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calibration::*;
+    use crate::constants::{HESTON_NAME, MERTON_NAME};
+    use crate::constraints::{
+        check_heston_parameters, check_merton_parameters, BlackScholesParameters, HestonParameters,
+        HESTON_CONSTRAINTS, MERTON_CONSTRAINTS,
+    };
+    use approx::*;
+    fn get_test_options() -> OptimizationOptions {
+        OptimizationOptions {
+            max_global_iterations: 20,
+            max_local_iterations: 500,
+            ..OptimizationOptions::default()
+        }
+    }
+    fn get_test_market(num_u: usize, dividend_yield: f64) -> CalibrationMarket {
+        CalibrationMarket {
+            asset: 100.0,
+            rate: 0.02,
+            dividend_yield,
+            num_u,
+            option_scale: 10.0,
+        }
+    }
+    fn get_heston_option_data(
+        strikes: &[f64],
+        maturity: f64,
+        dividend_yield: f64,
+    ) -> OptionQuoteMaturity {
+        let parameters = HestonParameters {
+            sigma: 0.2,
+            v0: 0.25,
+            speed: 1.5,
+            eta_v: 0.4,
+            rho: -0.5,
+        };
+        let results = get_option_results_as_json(
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
+            &CFParameters::Heston(parameters),
+            &PricingParameters {
+                num_u: 128,
+                scale: 10.0,
+                maturity,
+                rate: 0.02,
+                dividend_yield,
+                cash_dividend_shift: 0.0,
+            },
+            100.0,
+            strikes,
+        )
+        .unwrap();
+        OptionQuoteMaturity {
+            maturity,
+            option_data: results
+                .iter()
+                .map(
+                    |GraphElement {
+                         at_point, value, ..
+                     }| OptionQuote {
+                        strike: *at_point,
+                        price: Some(*value),
+                        ..OptionQuote::default()
+                    },
+                )
+                .collect(),
+        }
+    }
+    #[test]
+    fn test_calibration_market_from_parameters() {
+        let mut parameters = CalibrationParameters {
+            option_data: vec![],
+            asset: 100.0,
+            num_u: 7,
+            rate: 0.02,
+            dividend_yield: Some(0.01),
+            borrow_rate: Some(0.005),
+            objective: None,
+            truncation_scale: None,
+        };
+        assert_eq!(
+            CalibrationMarket::new(&parameters, 10.0),
+            CalibrationMarket {
+                asset: 100.0,
+                rate: 0.02,
+                dividend_yield: 0.015,
+                num_u: 128,
+                option_scale: 10.0,
+            }
+        );
+        parameters.dividend_yield = None;
+        parameters.borrow_rate = None;
+        parameters.truncation_scale = Some(5.0);
+        let market = CalibrationMarket::new(&parameters, 10.0);
+        assert_eq!(market.dividend_yield, 0.0);
+        assert_eq!(market.option_scale, 5.0);
+    }
+    #[test]
+    fn test_minimize_stays_in_bounds() {
+        let bounds = vec![
+            Bound {
+                lower: 1.0,
+                upper: 2.0,
+            },
+            Bound {
+                lower: -3.0,
+                upper: 3.0,
+            },
+        ];
+        let result = minimize(
+            |x| x[0].powi(2) + (x[1] - 0.5).powi(2),
+            &bounds,
+            &get_test_options(),
+        );
+        assert!((result.parameters[0] - 1.0).abs() < 0.0001);
+        assert!((result.parameters[1] - 0.5).abs() < 0.0001);
+        assert_eq!(
+            result.cost_history.len(),
+            result.global_iterations + result.local_iterations
+        );
+    }
+    #[test]
+    fn test_minimize_finds_global_minimum() {
+        //Rastrigin function, many local minima with global minimum at zero
+        let bounds = vec![
+            Bound {
+                lower: -5.12,
+                upper: 5.12,
+            };
+            2
+        ];
+        let result = minimize(
+            |x| {
+                20.0 + x
+                    .iter()
+                    .map(|v| v.powi(2) - 10.0 * (2.0 * std::f64::consts::PI * v).cos())
+                    .sum::<f64>()
+            },
+            &bounds,
+            &OptimizationOptions::default(),
+        );
+        assert!(result.cost < 0.0001);
+    }
+    #[test]
+    fn test_minimize_reports_progress() {
+        let bounds = vec![
+            Bound {
+                lower: -1.0,
+                upper: 1.0,
+            };
+            2
+        ];
+        let progress = OptimizationProgress::default();
+        assert!(progress.best_cost().is_nan());
+        let result = minimize_with_progress(
+            |x| x[0].powi(2) + x[1].powi(2),
+            &bounds,
+            &get_test_options(),
+            &progress,
+        );
+        assert_eq!(
+            progress.iterations(),
+            result.global_iterations + result.local_iterations
+        );
+        assert_eq!(progress.best_cost(), result.cost);
+    }
+    #[test]
+    fn test_minimize_cancelled() {
+        let bounds = vec![
+            Bound {
+                lower: -1.0,
+                upper: 1.0,
+            };
+            2
+        ];
+        let progress = OptimizationProgress::default();
+        progress.cancel();
+        let result = minimize_with_progress(
+            |x| x[0].powi(2) + x[1].powi(2),
+            &bounds,
+            &get_test_options(),
+            &progress,
+        );
+        assert_eq!(result.global_iterations, 0);
+        assert_eq!(result.local_iterations, 0);
+    }
+    #[test]
+    fn test_active_bounds() {
+        let bounds = vec![
+            Bound {
+                lower: 0.0,
+                upper: 1.0,
+            };
+            3
+        ];
+        let active = get_active_bounds(&[(0.0, "a"), (0.5, "b"), (1.0, "c")], &bounds);
+        assert_eq!(active.len(), 2);
+        assert_eq!(active[0].parameter, "a");
+        assert_eq!(active[0].bound, "lower");
+        assert_eq!(active[1].parameter, "c");
+        assert_eq!(active[1].bound, "upper");
+    }
+    #[test]
+    fn test_symmetric_eigen() {
+        let (eigenvalues, eigenvectors) = symmetric_eigen(&[vec![2.0, 1.0], vec![1.0, 2.0]]);
+        let mut sorted = eigenvalues.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_abs_diff_eq!(sorted[0], 1.0, epsilon = 0.0000001);
+        assert_abs_diff_eq!(sorted[1], 3.0, epsilon = 0.0000001);
+        for (k, eigenvalue) in eigenvalues.iter().enumerate() {
+            //A v = lambda v
+            let av = 2.0 * eigenvectors[0][k] + eigenvectors[1][k];
+            assert_abs_diff_eq!(av, eigenvalue * eigenvectors[0][k], epsilon = 0.0000001);
+        }
+    }
+    #[test]
+    fn test_parameter_uncertainty_linear_regression() {
+        //y = a + b x, for which the covariance is s^2 (X^T X)^-1
+        let x = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let y = [0.1, 0.9, 2.2, 2.8, 4.1];
+        let residual_fn = |params: &[f64]| -> Vec<f64> {
+            x.iter()
+                .zip(&y)
+                .map(|(xi, yi)| params[0] + params[1] * xi - yi)
+                .collect()
+        };
+        let bounds = vec![
+            Bound {
+                lower: -10.0,
+                upper: 10.0,
+            };
+            2
+        ];
+        //least squares solution
+        let parameters = vec![0.08, 0.99];
+        let jacobian = get_jacobian(residual_fn, &parameters, &bounds);
+        assert_abs_diff_eq!(jacobian[2][0], 1.0, epsilon = 0.0000001);
+        assert_abs_diff_eq!(jacobian[2][1], 2.0, epsilon = 0.0000001);
+        let residuals = residual_fn(&parameters);
+        let uncertainty = get_parameter_uncertainty(&jacobian, &residuals, &["a", "b"]);
+        let variance = residuals.iter().map(|r| r.powi(2)).sum::<f64>() / 3.0;
+        //X^T X = [[5, 10], [10, 30]] with determinant 50
+        assert_abs_diff_eq!(
+            uncertainty.covariance[0][0],
+            variance * 30.0 / 50.0,
+            epsilon = 0.0000001
+        );
+        assert_abs_diff_eq!(
+            uncertainty.covariance[0][1],
+            -variance * 10.0 / 50.0,
+            epsilon = 0.0000001
+        );
+        assert_abs_diff_eq!(
+            uncertainty.standard_errors[1],
+            (variance * 5.0 / 50.0).sqrt(),
+            epsilon = 0.0000001
+        );
+        assert_abs_diff_eq!(
+            uncertainty.correlation[0][1],
+            -10.0 / (30.0_f64 * 5.0).sqrt(),
+            epsilon = 0.0000001
+        );
+    }
+    #[test]
+    fn test_parameter_uncertainty_not_identifiable() {
+        //second parameter has no effect on the residuals
+        let jacobian = vec![vec![1.0, 0.0], vec![2.0, 0.0], vec![3.0, 0.0]];
+        let uncertainty = get_parameter_uncertainty(&jacobian, &[0.1, -0.1, 0.05], &["a", "b"]);
+        assert!(uncertainty.condition_number.is_nan());
+        assert!(uncertainty.standard_errors[1].is_nan());
+    }
+    #[test]
+    fn test_calibration_heston() {
+        let strikes = vec![80.0, 90.0, 95.0, 100.0, 105.0, 110.0, 120.0];
+        let option_data = vec![
+            get_heston_option_data(&strikes, 0.5, 0.0),
+            get_heston_option_data(&strikes, 1.0, 0.0),
+        ];
+        let result = get_calibration_results_as_json(
+            &CFParameters::from_model(HESTON_NAME).unwrap(),
+            CalibrationObjective::Price,
+            &get_test_market(128, 0.0),
+            &option_data,
+            &get_test_options(),
+            &OptimizationProgress::default(),
+        )
+        .unwrap();
+        assert!(result.final_cost_value < 0.05);
+        assert!(!result.diagnostics.cost_history.is_empty());
+        let uncertainty = &result.uncertainty;
+        assert_eq!(
+            uncertainty.parameter_names,
+            vec!["sigma", "v0", "speed", "eta_v", "rho"]
+        );
+        assert_eq!(uncertainty.standard_errors.len(), 5);
+        for i in 0..5 {
+            assert_abs_diff_eq!(uncertainty.correlation[i][i], 1.0, epsilon = 0.000001);
+        }
+        assert!(uncertainty.condition_number >= 1.0);
+        if let CFParameters::Heston(cf_params) = result.parameters {
+            assert!(check_heston_parameters(&cf_params, &HESTON_CONSTRAINTS).is_ok());
+        } else {
+            panic!("expected heston parameters");
+        }
+    }
+    #[test]
+    fn test_calibration_merton_in_bounds() {
+        let strikes = vec![80.0, 90.0, 100.0, 110.0, 120.0];
+        let option_data = vec![get_heston_option_data(&strikes, 1.0, 0.0)];
+        let options = OptimizationOptions {
+            max_global_iterations: 3,
+            max_local_iterations: 100,
+            ..OptimizationOptions::default()
+        };
+        let result = get_calibration_results_as_json(
+            &CFParameters::from_model(MERTON_NAME).unwrap(),
+            CalibrationObjective::RelativePrice,
+            &get_test_market(64, 0.0),
+            &option_data,
+            &options,
+            &OptimizationProgress::default(),
+        )
+        .unwrap();
+        if let CFParameters::Merton(cf_params) = result.parameters {
+            assert!(check_merton_parameters(&cf_params, &MERTON_CONSTRAINTS).is_ok());
+        } else {
+            panic!("expected merton parameters");
+        }
+    }
+    #[test]
+    fn test_calibration_cancelled() {
+        let strikes = vec![90.0, 100.0, 110.0];
+        let option_data = vec![get_heston_option_data(&strikes, 1.0, 0.0)];
+        let progress = OptimizationProgress::default();
+        progress.cancel();
+        let result = get_calibration_results_as_json(
+            &CFParameters::from_model(HESTON_NAME).unwrap(),
+            CalibrationObjective::Price,
+            &get_test_market(64, 0.0),
+            &option_data,
+            &get_test_options(),
+            &progress,
+        );
+        assert_eq!(
+            result.err().unwrap(),
+            ParameterError::new(&ErrorType::OptimizationError(
+                "Calibration was cancelled".to_string()
+            ))
+        );
+    }
+    #[test]
+    fn test_calibration_heston_implied_volatility() {
+        let strikes = vec![80.0, 90.0, 95.0, 100.0, 105.0, 110.0, 120.0];
+        let maturity = 1.0;
+        let mut option_data = get_heston_option_data(&strikes, maturity, 0.0);
+        //quote half of the strikes in implied volatility terms
+        option_data
+            .option_data
+            .iter_mut()
+            .step_by(2)
+            .for_each(|quote| {
+                quote.implied_volatility = Some(
+                    black_scholes::call_iv(
+                        quote.price.unwrap(),
+                        100.0,
+                        quote.strike,
+                        0.02,
+                        maturity,
+                    )
+                    .unwrap(),
+                );
+                quote.price = None;
+            });
+        let result = get_calibration_results_as_json(
+            &CFParameters::from_model(HESTON_NAME).unwrap(),
+            CalibrationObjective::ImpliedVolatility,
+            &get_test_market(128, 0.0),
+            &[option_data],
+            &get_test_options(),
+            &OptimizationProgress::default(),
+        )
+        .unwrap();
+        //in units of volatility
+        assert!(result.final_cost_value < 0.005);
+    }
+    #[test]
+    fn test_calibration_heston_vega_weighted() {
+        let strikes = vec![80.0, 90.0, 95.0, 100.0, 105.0, 110.0, 120.0];
+        let option_data = vec![get_heston_option_data(&strikes, 1.0, 0.0)];
+        let result = get_calibration_results_as_json(
+            &CFParameters::from_model(HESTON_NAME).unwrap(),
+            CalibrationObjective::VegaWeighted,
+            &get_test_market(128, 0.0),
+            &option_data,
+            &get_test_options(),
+            &OptimizationProgress::default(),
+        )
+        .unwrap();
+        assert!(result.final_cost_value < 0.005);
+    }
+    #[test]
+    fn test_market_quote_requires_price_or_implied_volatility() {
+        let quote = OptionQuote {
+            strike: 100.0,
+            ..OptionQuote::default()
+        };
+        let result = get_market_quote(
+            CalibrationObjective::Price,
+            &get_test_market(64, 0.0),
+            1.0,
+            &quote,
+        );
+        assert!(result.is_err());
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Parameter price does not exist."
+        );
+    }
+    #[test]
+    fn test_market_quote_from_implied_volatility() {
+        let quote = OptionQuote {
+            strike: 100.0,
+            implied_volatility: Some(0.3),
+            ..OptionQuote::default()
+        };
+        let result = get_market_quote(
+            CalibrationObjective::ImpliedVolatility,
+            &get_test_market(64, 0.0),
+            1.0,
+            &quote,
+        )
+        .unwrap();
+        assert_eq!(
+            result.price,
+            black_scholes::call(100.0, 100.0, 0.02, 0.3, 1.0)
+        );
+        assert_eq!(result.implied_volatility, 0.3);
+    }
+    #[test]
+    fn test_market_quote_from_implied_volatility_with_dividends() {
+        let quote = OptionQuote {
+            strike: 110.0,
+            implied_volatility: Some(0.3),
+            ..OptionQuote::default()
+        };
+        let market = get_test_market(256, 0.03);
+        let result = get_market_quote(
+            CalibrationObjective::ImpliedVolatility,
+            &market,
+            1.0,
+            &quote,
+        )
+        .unwrap();
+        let model = get_option_results_as_json(
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
+            &CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+            &market.get_pricing_parameters(1.0),
+            market.asset,
+            &[quote.strike],
+        )
+        .unwrap();
+        assert_abs_diff_eq!(result.price, model[0].value, epsilon = 0.0001);
+        let error = get_quote_error(
+            CalibrationObjective::ImpliedVolatility,
+            model[0].value,
+            &result,
+            &market,
+            quote.strike,
+            1.0,
+        );
+        assert_abs_diff_eq!(error, 0.0, epsilon = 0.0001);
+    }
+    #[test]
+    fn test_calibration_heston_with_dividends() {
+        let strikes = vec![80.0, 90.0, 95.0, 100.0, 105.0, 110.0, 120.0];
+        let option_data = vec![get_heston_option_data(&strikes, 1.0, 0.03)];
+        let result = get_calibration_results_as_json(
+            &CFParameters::from_model(HESTON_NAME).unwrap(),
+            CalibrationObjective::ImpliedVolatility,
+            &get_test_market(128, 0.03),
+            &option_data,
+            &get_test_options(),
+            &OptimizationProgress::default(),
+        )
+        .unwrap();
+        assert!(result.final_cost_value < 0.005);
+    }
+    #[test]
+    fn test_market_quote_below_intrinsic() {
+        let quote = OptionQuote {
+            strike: 50.0,
+            price: Some(10.0),
+            ..OptionQuote::default()
+        };
+        //no implied volatility needed for price objective
+        assert!(get_market_quote(
+            CalibrationObjective::Price,
+            &get_test_market(64, 0.0),
+            1.0,
+            &quote
+        )
+        .is_ok());
+        let result = get_market_quote(
+            CalibrationObjective::ImpliedVolatility,
+            &get_test_market(64, 0.0),
+            1.0,
+            &quote,
+        );
+        assert!(result.is_err());
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Root does not exist for implied volatility"
+        );
+    }
+    #[test]
+    fn test_market_quote_bid_ask() {
+        let quote = OptionQuote {
+            strike: 100.0,
+            bid: Some(5.0),
+            ask: Some(6.0),
+            open_interest: Some(300.0),
+            ..OptionQuote::default()
+        };
+        let result = get_market_quote(
+            CalibrationObjective::BidAsk,
+            &get_test_market(64, 0.0),
+            1.0,
+            &quote,
+        )
+        .unwrap();
+        assert_eq!(result.price, 5.5);
+        assert_eq!(result.mid, 5.5);
+        assert_eq!(result.weight, 300.0);
+        let inside = get_quote_error(
+            CalibrationObjective::BidAsk,
+            5.8,
+            &result,
+            &get_test_market(64, 0.0),
+            100.0,
+            1.0,
+        );
+        assert_eq!(inside, 0.0);
+        let outside = get_quote_error(
+            CalibrationObjective::BidAsk,
+            4.5,
+            &result,
+            &get_test_market(64, 0.0),
+            100.0,
+            1.0,
+        );
+        assert_eq!(outside, 0.5);
+    }
+    #[test]
+    fn test_market_quote_bid_above_ask() {
+        let quote = OptionQuote {
+            strike: 100.0,
+            bid: Some(6.0),
+            ask: Some(5.0),
+            ..OptionQuote::default()
+        };
+        let result = get_market_quote(
+            CalibrationObjective::BidAsk,
+            &get_test_market(64, 0.0),
+            1.0,
+            &quote,
+        );
+        assert!(result.is_err());
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Parameter bid out of bounds."
+        );
+    }
+    #[test]
+    fn test_calibration_heston_bid_ask() {
+        let strikes = vec![80.0, 90.0, 95.0, 100.0, 105.0, 110.0, 120.0];
+        let mut option_data = get_heston_option_data(&strikes, 1.0, 0.0);
+        option_data.option_data.iter_mut().for_each(|quote| {
+            let price = quote.price.unwrap();
+            quote.bid = Some(price * 0.98);
+            quote.ask = Some(price * 1.02);
+            quote.price = None;
+        });
+        let result = get_calibration_results_as_json(
+            &CFParameters::from_model(HESTON_NAME).unwrap(),
+            CalibrationObjective::BidAsk,
+            &get_test_market(128, 0.0),
+            &[option_data],
+            &get_test_options(),
+            &OptimizationProgress::default(),
+        )
+        .unwrap();
+        assert!(result.final_cost_value < 0.01);
+        let fitted = &result.fitted_quotes[0].option_data;
+        assert_eq!(fitted.len(), strikes.len());
+        fitted.iter().zip(&strikes).for_each(|(quote, strike)| {
+            assert_eq!(quote.strike, *strike);
+            assert_eq!(quote.residual, quote.fitted_price - quote.market_mid);
+        });
+    }
+}
@@ -0,0 +1,786 @@
+//! Characteristic functions for models that are not
+//! provided by `cf_functions`.  These follow the same
+//! conventions: `u` is the complex argument of the
+//! moment generating function, and the time change is a
+//! CIR process with long run mean of one.
+use crate::constraints::HestonParameters;
+use cf_functions::affine_process::generic_leverage_diffusion;
+use cf_functions::cgmy::cgmy_expectation;
+use cf_functions::gauss::{gauss_log_cf_cmp, heston_log_cf};
+use cf_functions::merton::merton_log_cf;
+use num_complex::Complex;
+use special::Gamma;
+
+/// Returns log of the Kou double exponential jump
+/// characteristic function, without drift or diffusion
+pub fn kou_log_cf(
+    u: &Complex<f64>,
+    lambda: f64,
+    p: f64,
+    eta_up: f64,
+    eta_down: f64,
+) -> Complex<f64> {
+    lambda * (p * eta_up / (eta_up - u) + (1.0 - p) * eta_down / (eta_down + u) - 1.0)
+}
+
+/// Returns log of the Kou characteristic function with
+/// a diffusion component, under the risk neutral measure
+pub fn kou_log_risk_neutral_cf(
+    u: &Complex<f64>,
+    lambda: f64,
+    p: f64,
+    eta_up: f64,
+    eta_down: f64,
+    rate: f64,
+    sigma: f64,
+) -> Complex<f64> {
+    let cmp_mu = rate
+        - 0.5 * sigma.powi(2)
+        - kou_log_cf(&Complex::new(1.0, 0.0), lambda, p, eta_up, eta_down);
+    gauss_log_cf_cmp(u, &cmp_mu, sigma) + kou_log_cf(u, lambda, p, eta_up, eta_down)
+}
+
+/// Returns the time changed Kou characteristic function
+/// with correlation between the time change diffusion
+/// and the asset diffusion
+/// # Examples
+///
+/// ```
+/// extern crate num_complex;
+/// extern crate utils;
+/// use num_complex::Complex;
+/// use utils::cf_models;
+/// # fn main() {
+/// let time_change = cf_models::TimeChange {
+///     sigma: 0.2, v0: 0.9, speed: 0.5, eta_v: 0.3, rho: -0.5
+/// };
+/// let cf = cf_models::kou_time_change_cf(1.0, 0.05, 0.5, 0.4, 10.0, 5.0, &time_change);
+/// let value_of_cf = cf(&Complex::new(0.05, -0.5));
+/// # }
+/// ```
+pub fn kou_time_change_cf(
+    t: f64,
+    rate: f64,
+    lambda: f64,
+    p: f64,
+    eta_up: f64,
+    eta_down: f64,
+    time_change: &TimeChange,
+) -> impl Fn(&Complex<f64>) -> Complex<f64> {
+    let TimeChange {
+        sigma,
+        v0,
+        speed,
+        eta_v,
+        rho,
+    } = *time_change;
+    move |u| {
+        (rate * t * u
+            + generic_leverage_diffusion(
+                u,
+                &|u| kou_log_risk_neutral_cf(u, lambda, p, eta_up, eta_down, 0.0, sigma),
+                t,
+                sigma,
+                v0,
+                speed,
+                eta_v,
+                rho,
+            ))
+        .exp()
+    }
+}
+
+/// Returns log of the variance gamma characteristic
+/// function, without drift or diffusion
+pub fn vg_log_cf(u: &Complex<f64>, sigma_vg: f64, nu: f64, theta: f64) -> Complex<f64> {
+    -(1.0 - theta * nu * u - 0.5 * sigma_vg.powi(2) * nu * u * u).ln() / nu
+}
+
+/// Returns log of the variance gamma characteristic
+/// function with a diffusion component, under the risk
+/// neutral measure
+pub fn vg_log_risk_neutral_cf(
+    u: &Complex<f64>,
+    sigma_vg: f64,
+    nu: f64,
+    theta: f64,
+    rate: f64,
+    sigma: f64,
+) -> Complex<f64> {
+    let cmp_mu =
+        rate - 0.5 * sigma.powi(2) - vg_log_cf(&Complex::new(1.0, 0.0), sigma_vg, nu, theta);
+    gauss_log_cf_cmp(u, &cmp_mu, sigma) + vg_log_cf(u, sigma_vg, nu, theta)
+}
+
+/// Returns the time changed variance gamma
+/// characteristic function with correlation between the
+/// time change diffusion and the asset diffusion
+/// # Examples
+///
+/// ```
+/// extern crate num_complex;
+/// extern crate utils;
+/// use num_complex::Complex;
+/// use utils::cf_models;
+/// # fn main() {
+/// let time_change = cf_models::TimeChange {
+///     sigma: 0.1, v0: 0.9, speed: 0.5, eta_v: 0.3, rho: -0.5
+/// };
+/// let cf = cf_models::vg_time_change_cf(1.0, 0.05, 0.12, 0.2, -0.14, &time_change);
+/// let value_of_cf = cf(&Complex::new(0.05, -0.5));
+/// # }
+/// ```
+pub fn vg_time_change_cf(
+    t: f64,
+    rate: f64,
+    sigma_vg: f64,
+    nu: f64,
+    theta: f64,
+    time_change: &TimeChange,
+) -> impl Fn(&Complex<f64>) -> Complex<f64> {
+    let TimeChange {
+        sigma,
+        v0,
+        speed,
+        eta_v,
+        rho,
+    } = *time_change;
+    move |u| {
+        (rate * t * u
+            + generic_leverage_diffusion(
+                u,
+                &|u| vg_log_risk_neutral_cf(u, sigma_vg, nu, theta, 0.0, sigma),
+                t,
+                sigma,
+                v0,
+                speed,
+                eta_v,
+                rho,
+            ))
+        .exp()
+    }
+}
+
+/// Returns log of the normal inverse Gaussian
+/// characteristic function, without drift or diffusion
+pub fn nig_log_cf(u: &Complex<f64>, alpha: f64, beta: f64, delta: f64) -> Complex<f64> {
+    delta * ((alpha.powi(2) - beta.powi(2)).sqrt() - (alpha.powi(2) - (beta + u).powi(2)).sqrt())
+}
+
+/// Returns log of the normal inverse Gaussian
+/// characteristic function with a diffusion component,
+/// under the risk neutral measure
+pub fn nig_log_risk_neutral_cf(
+    u: &Complex<f64>,
+    alpha: f64,
+    beta: f64,
+    delta: f64,
+    rate: f64,
+    sigma: f64,
+) -> Complex<f64> {
+    let cmp_mu =
+        rate - 0.5 * sigma.powi(2) - nig_log_cf(&Complex::new(1.0, 0.0), alpha, beta, delta);
+    gauss_log_cf_cmp(u, &cmp_mu, sigma) + nig_log_cf(u, alpha, beta, delta)
+}
+
+/// Returns the time changed normal inverse Gaussian
+/// characteristic function with correlation between the
+/// time change diffusion and the asset diffusion
+/// # Examples
+///
+/// ```
+/// extern crate num_complex;
+/// extern crate utils;
+/// use num_complex::Complex;
+/// use utils::cf_models;
+/// # fn main() {
+/// let time_change = cf_models::TimeChange {
+///     sigma: 0.1, v0: 0.9, speed: 0.5, eta_v: 0.3, rho: -0.5
+/// };
+/// let cf = cf_models::nig_time_change_cf(1.0, 0.05, 15.0, -5.0, 0.5, &time_change);
+/// let value_of_cf = cf(&Complex::new(0.05, -0.5));
+/// # }
+/// ```
+pub fn nig_time_change_cf(
+    t: f64,
+    rate: f64,
+    alpha: f64,
+    beta: f64,
+    delta: f64,
+    time_change: &TimeChange,
+) -> impl Fn(&Complex<f64>) -> Complex<f64> {
+    let TimeChange {
+        sigma,
+        v0,
+        speed,
+        eta_v,
+        rho,
+    } = *time_change;
+    move |u| {
+        (rate * t * u
+            + generic_leverage_diffusion(
+                u,
+                &|u| nig_log_risk_neutral_cf(u, alpha, beta, delta, 0.0, sigma),
+                t,
+                sigma,
+                v0,
+                speed,
+                eta_v,
+                rho,
+            ))
+        .exp()
+    }
+}
+
+/// Returns the Bates characteristic function: Heston
+/// stochastic volatility with lognormal jumps that are
+/// independent of the variance process.
+/// # Examples
+///
+/// ```
+/// extern crate num_complex;
+/// extern crate utils;
+/// use num_complex::Complex;
+/// use utils::cf_models;
+/// use utils::constraints::HestonParameters;
+/// # fn main() {
+/// let heston = HestonParameters {
+///     sigma: 0.2, v0: 0.04, speed: 1.5, eta_v: 0.5, rho: -0.5
+/// };
+/// let cf = cf_models::bates_cf(1.0, 0.05, 0.5, -0.1, 0.1, &heston);
+/// let value_of_cf = cf(&Complex::new(0.05, -0.5));
+/// # }
+/// ```
+pub fn bates_cf(
+    t: f64,
+    rate: f64,
+    lambda: f64,
+    mu_l: f64,
+    sig_l: f64,
+    heston: &HestonParameters,
+) -> impl Fn(&Complex<f64>) -> Complex<f64> {
+    let HestonParameters {
+        sigma,
+        v0,
+        speed,
+        eta_v,
+        rho,
+    } = *heston;
+    let jump_compensator = merton_log_cf(&Complex::new(1.0, 0.0), lambda, mu_l, sig_l);
+    move |u| {
+        (rate * t * u
+            + heston_log_cf(u, t, sigma, v0, speed, eta_v, rho)
+            + t * (merton_log_cf(u, lambda, mu_l, sig_l) - u * jump_compensator))
+            .exp()
+    }
+}
+
+/// Returns the derivative of the Heston log characteristic
+/// function with respect to `v0`.  The log characteristic
+/// function is affine in `v0`, so the difference between
+/// `v0` of one and zero is exact.
+pub fn heston_log_cf_dv0(
+    u: &Complex<f64>,
+    t: f64,
+    sigma: f64,
+    speed: f64,
+    eta_v: f64,
+    rho: f64,
+) -> Complex<f64> {
+    heston_log_cf(u, t, sigma, 1.0, speed, eta_v, rho)
+        - heston_log_cf(u, t, sigma, 0.0, speed, eta_v, rho)
+}
+
+/// First, second and fourth cumulants of the log asset
+/// return, which set the truncation range of the inversion
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cumulants {
+    pub c1: f64,
+    pub c2: f64,
+    pub c4: f64,
+}
+
+impl Cumulants {
+    /// Truncation range of the log return, following Fang
+    /// and Oosterlee's c1 +/- scale * sqrt(c2 + sqrt(c4))
+    pub fn range(&self, scale: f64) -> (f64, f64) {
+        let half_width = scale * (self.c2 + self.c4.max(0.0).sqrt()).sqrt();
+        (self.c1 - half_width, self.c1 + half_width)
+    }
+}
+
+impl std::ops::Add for Cumulants {
+    type Output = Cumulants;
+    /// Cumulants of the sum of independent returns
+    fn add(self, other: Cumulants) -> Cumulants {
+        Cumulants {
+            c1: self.c1 + other.c1,
+            c2: self.c2 + other.c2,
+            c4: self.c4 + other.c4,
+        }
+    }
+}
+
+/// Cumulants of a Lévy process over one unit of time
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LevyCumulants {
+    pub k1: f64,
+    pub k2: f64,
+    pub k3: f64,
+    pub k4: f64,
+}
+
+impl LevyCumulants {
+    /// Adds a diffusion with volatility `sigma` to pure
+    /// jumps, along with the drift that makes the exponential
+    /// of the process a martingale.  `jump_log_mgf` is the log
+    /// of the jumps' moment generating function at one.
+    pub fn risk_neutral(&self, sigma: f64, jump_log_mgf: f64) -> LevyCumulants {
+        LevyCumulants {
+            k1: self.k1 - 0.5 * sigma.powi(2) - jump_log_mgf,
+            k2: self.k2 + sigma.powi(2),
+            ..*self
+        }
+    }
+    /// Cumulants of the process multiplied by `scale`
+    pub fn scale(&self, scale: f64) -> LevyCumulants {
+        LevyCumulants {
+            k1: self.k1 * scale,
+            k2: self.k2 * scale.powi(2),
+            k3: self.k3 * scale.powi(3),
+            k4: self.k4 * scale.powi(4),
+        }
+    }
+}
+
+/// CIR time change with a long run mean of one, whose
+/// diffusion has correlation `rho` with the asset diffusion
+/// of volatility `sigma`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeChange {
+    pub sigma: f64,
+    pub v0: f64,
+    pub speed: f64,
+    pub eta_v: f64,
+    pub rho: f64,
+}
+
+impl TimeChange {
+    /// Calendar time, which leaves a Lévy process unchanged
+    pub fn none() -> TimeChange {
+        TimeChange {
+            sigma: 0.0,
+            v0: 1.0,
+            speed: 0.0,
+            eta_v: 0.0,
+            rho: 0.0,
+        }
+    }
+    /// Heston's variance as a time change of Black-Scholes,
+    /// following `cf_functions::gauss::heston_log_cf`
+    pub fn heston(sigma: f64, v0: f64, speed: f64, eta_v: f64, rho: f64) -> TimeChange {
+        TimeChange {
+            sigma,
+            v0: v0 / sigma.powi(2),
+            speed,
+            eta_v: eta_v / sigma,
+            rho,
+        }
+    }
+}
+
+/// Below this `speed * t` the moments of the time change
+/// use their Taylor series, avoiding cancellation
+const SERIES_THRESHOLD: f64 = 0.01;
+
+/// Returns the mean and variance of the integrated time
+/// change over `t`, and its covariance with the integral of
+/// the square root of the time change against the time
+/// change's Brownian motion
+fn get_time_change_moments(t: f64, time_change: &TimeChange) -> (f64, f64, f64) {
+    let TimeChange {
+        v0, speed, eta_v, ..
+    } = *time_change;
+    let x = speed * t;
+    if x < SERIES_THRESHOLD {
+        let mean = t * (v0 + (1.0 - v0) * x * (0.5 - x / 6.0 + x.powi(2) / 24.0));
+        let variance = eta_v.powi(2)
+            * t.powi(3)
+            * (v0 / 3.0
+                + x * (1.0 / 12.0 - v0 / 3.0)
+                + x.powi(2) * (11.0 * v0 / 60.0 - 1.0 / 15.0)
+                + x.powi(3) * (11.0 / 360.0 - 13.0 * v0 / 180.0));
+        let covariance = eta_v
+            * t.powi(2)
+            * (v0 / 2.0
+                + x * (1.0 / 6.0 - v0 / 3.0)
+                + x.powi(2) * (v0 / 8.0 - 1.0 / 12.0)
+                + x.powi(3) * (1.0 / 40.0 - v0 / 30.0));
+        return (mean, variance, covariance);
+    }
+    let decay = (-x).exp();
+    let mean = t * (1.0 + (v0 - 1.0) * (1.0 - decay) / x);
+    let variance = eta_v.powi(2) * t.powi(3) / (2.0 * x.powi(3))
+        * (2.0 * x + 2.0 * v0 - 5.0
+            + 4.0 * (1.0 + x * (1.0 - v0)) * decay
+            + (1.0 - 2.0 * v0) * decay.powi(2));
+    let covariance =
+        eta_v * t.powi(2) / x.powi(2) * (x + v0 - 2.0 + (2.0 - v0 + x * (1.0 - v0)) * decay);
+    (mean, variance, covariance)
+}
+
+/// Returns the cumulants of the log return at `t` of a
+/// Lévy process run on the time change, plus the drift
+/// `rate`.  The first two cumulants are exact; the fourth
+/// keeps the terms in the mean and variance of the time
+/// change and ignores the correlation.
+pub fn time_change_cumulants(
+    t: f64,
+    rate: f64,
+    levy: &LevyCumulants,
+    time_change: &TimeChange,
+) -> Cumulants {
+    let LevyCumulants { k1, k2, k3, k4 } = *levy;
+    let (mean, variance, covariance) = get_time_change_moments(t, time_change);
+    let leverage = 2.0 * k1 * time_change.sigma * time_change.rho * covariance;
+    Cumulants {
+        c1: rate * t + k1 * mean,
+        c2: k2 * mean + k1.powi(2) * variance + leverage,
+        c4: k4 * mean + (4.0 * k1 * k3 + 3.0 * k2.powi(2)) * variance,
+    }
+}
+
+/// Returns the cumulants of Merton's lognormal jumps
+pub fn merton_cumulants(lambda: f64, mu_l: f64, sig_l: f64) -> LevyCumulants {
+    let (mu2, sig2) = (mu_l.powi(2), sig_l.powi(2));
+    LevyCumulants {
+        k1: lambda * mu_l,
+        k2: lambda * (mu2 + sig2),
+        k3: lambda * mu_l * (mu2 + 3.0 * sig2),
+        k4: lambda * (mu2.powi(2) + 6.0 * mu2 * sig2 + 3.0 * sig2.powi(2)),
+    }
+}
+
+/// Returns the cumulants of Kou's double exponential jumps
+pub fn kou_cumulants(lambda: f64, p: f64, eta_up: f64, eta_down: f64) -> LevyCumulants {
+    //the n-th moment of the jumps, times the jump intensity
+    let moment = |n: i32, factorial: f64| {
+        lambda * factorial * (p / eta_up.powi(n) + (1.0 - p) * (-1.0f64).powi(n) / eta_down.powi(n))
+    };
+    LevyCumulants {
+        k1: moment(1, 1.0),
+        k2: moment(2, 2.0),
+        k3: moment(3, 6.0),
+        k4: moment(4, 24.0),
+    }
+}
+
+/// Returns the cumulants of the variance gamma process
+pub fn vg_cumulants(sigma_vg: f64, nu: f64, theta: f64) -> LevyCumulants {
+    let sigma2 = sigma_vg.powi(2);
+    LevyCumulants {
+        k1: theta,
+        k2: sigma2 + nu * theta.powi(2),
+        k3: 2.0 * theta.powi(3) * nu.powi(2) + 3.0 * sigma2 * theta * nu,
+        k4: 3.0 * sigma2.powi(2) * nu
+            + 12.0 * sigma2 * theta.powi(2) * nu.powi(2)
+            + 6.0 * theta.powi(4) * nu.powi(3),
+    }
+}
+
+/// Returns the cumulants of the normal inverse Gaussian
+/// process
+pub fn nig_cumulants(alpha: f64, beta: f64, delta: f64) -> LevyCumulants {
+    let alpha2 = alpha.powi(2);
+    let gamma = (alpha2 - beta.powi(2)).sqrt();
+    LevyCumulants {
+        k1: delta * beta / gamma,
+        k2: delta * alpha2 / gamma.powi(3),
+        k3: 3.0 * delta * alpha2 * beta / gamma.powi(5),
+        k4: 3.0 * delta * alpha2 * (alpha2 + 4.0 * beta.powi(2)) / gamma.powi(7),
+    }
+}
+
+/// Returns the cumulants of the CGMY process.  Like
+/// `cf_functions::cgmy::cgmy_log_cf`, there are no jumps
+/// when `y` is one.
+pub fn cgmy_cumulants(c: f64, g: f64, m: f64, y: f64) -> LevyCumulants {
+    if (y - 1.0).abs() < f64::EPSILON {
+        return LevyCumulants::default();
+    }
+    let cumulant = |n: i32| {
+        let n_f64 = f64::from(n);
+        c * Gamma::gamma(n_f64 - y) * (m.powf(y - n_f64) + (-1.0f64).powi(n) * g.powf(y - n_f64))
+    };
+    LevyCumulants {
+        k1: cgmy_expectation(c, g, m, y),
+        k2: cumulant(2),
+        k3: cumulant(3),
+        k4: cumulant(4),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cf_models::*;
+    use approx::*;
+    /// Step for the finite differences, relative to the
+    /// standard deviation of the log return
+    const CUMULANT_STEP: f64 = 0.05;
+    /// Step used to estimate the standard deviation
+    const INITIAL_CUMULANT_STEP: f64 = 0.01;
+    /// Returns the first four cumulants of any characteristic
+    /// function, using central differences of the log
+    /// characteristic function along the imaginary axis
+    fn numerical_cumulants(cf: &impl Fn(&Complex<f64>) -> Complex<f64>) -> LevyCumulants {
+        let log_cf = |u: f64| cf(&Complex::new(0.0, u)).ln();
+        let second_cumulant = |step: f64| {
+            (log_cf(2.0 * step) - 16.0 * log_cf(step) + 30.0 * log_cf(0.0) - 16.0 * log_cf(-step)
+                + log_cf(-2.0 * step))
+            .re / (12.0 * step.powi(2))
+        };
+        let step = match second_cumulant(INITIAL_CUMULANT_STEP) {
+            c2 if c2 > 0.0 => CUMULANT_STEP / c2.sqrt(),
+            _ => INITIAL_CUMULANT_STEP,
+        };
+        let k1 = (-log_cf(2.0 * step) + 8.0 * log_cf(step) - 8.0 * log_cf(-step)
+            + log_cf(-2.0 * step))
+        .im / (12.0 * step);
+        let k3 = -(-log_cf(3.0 * step) + 8.0 * log_cf(2.0 * step) - 13.0 * log_cf(step)
+            + 13.0 * log_cf(-step)
+            - 8.0 * log_cf(-2.0 * step)
+            + log_cf(-3.0 * step))
+        .im / (8.0 * step.powi(3));
+        let k4 = (-log_cf(3.0 * step) + 12.0 * log_cf(2.0 * step) - 39.0 * log_cf(step)
+            + 56.0 * log_cf(0.0)
+            - 39.0 * log_cf(-step)
+            + 12.0 * log_cf(-2.0 * step)
+            - log_cf(-3.0 * step))
+        .re / (6.0 * step.powi(4));
+        LevyCumulants {
+            k1,
+            k2: second_cumulant(step),
+            k3,
+            k4,
+        }
+    }
+    fn assert_close(analytic: f64, numeric: f64) {
+        assert_abs_diff_eq!(
+            analytic,
+            numeric,
+            epsilon = 0.002 * numeric.abs().max(0.0001)
+        );
+    }
+    fn assert_levy_cumulants(analytic: &LevyCumulants, numeric: &LevyCumulants) {
+        assert_close(analytic.k1, numeric.k1);
+        assert_close(analytic.k2, numeric.k2);
+        assert_close(analytic.k3, numeric.k3);
+        assert_close(analytic.k4, numeric.k4);
+    }
+    /// Only the first two cumulants are exact under a time
+    /// change
+    fn assert_time_change_cumulants(analytic: &Cumulants, numeric: &LevyCumulants) {
+        assert_close(analytic.c1, numeric.k1);
+        assert_close(analytic.c2, numeric.k2);
+    }
+    #[test]
+    fn merton_cumulants_match_differences() {
+        let (lambda, mu_l, sig_l) = (0.5, -0.1, 0.2);
+        let cf = |u: &Complex<f64>| merton_log_cf(u, lambda, mu_l, sig_l).exp();
+        assert_levy_cumulants(
+            &merton_cumulants(lambda, mu_l, sig_l),
+            &numerical_cumulants(&cf),
+        );
+    }
+    #[test]
+    fn kou_cumulants_match_differences() {
+        let (lambda, p, eta_up, eta_down) = (0.8, 0.3, 8.0, 4.0);
+        let cf = |u: &Complex<f64>| kou_log_cf(u, lambda, p, eta_up, eta_down).exp();
+        assert_levy_cumulants(
+            &kou_cumulants(lambda, p, eta_up, eta_down),
+            &numerical_cumulants(&cf),
+        );
+    }
+    #[test]
+    fn vg_cumulants_match_differences() {
+        let (sigma_vg, nu, theta) = (0.12, 0.2, -0.14);
+        let cf = |u: &Complex<f64>| vg_log_cf(u, sigma_vg, nu, theta).exp();
+        assert_levy_cumulants(
+            &vg_cumulants(sigma_vg, nu, theta),
+            &numerical_cumulants(&cf),
+        );
+    }
+    #[test]
+    fn nig_cumulants_match_differences() {
+        let (alpha, beta, delta) = (15.0, -5.0, 0.5);
+        let cf = |u: &Complex<f64>| nig_log_cf(u, alpha, beta, delta).exp();
+        assert_levy_cumulants(
+            &nig_cumulants(alpha, beta, delta),
+            &numerical_cumulants(&cf),
+        );
+    }
+    #[test]
+    fn cgmy_cumulants_match_differences() {
+        let (c, g, m, y) = (0.5, 4.0, 3.0, 0.6);
+        let cf = |u: &Complex<f64>| cf_functions::cgmy::cgmy_log_cf(u, c, g, m, y).exp();
+        assert_levy_cumulants(&cgmy_cumulants(c, g, m, y), &numerical_cumulants(&cf));
+    }
+    #[test]
+    fn risk_neutral_cumulants_match_differences() {
+        let (t, rate, lambda, p, eta_up, eta_down, sigma) = (0.7, 0.05, 0.8, 0.3, 8.0, 4.0, 0.2);
+        let time_change = TimeChange {
+            sigma,
+            v0: 1.0,
+            speed: 0.5,
+            eta_v: 0.0,
+            rho: 0.0,
+        };
+        let cf = kou_time_change_cf(t, rate, lambda, p, eta_up, eta_down, &time_change);
+        let jump_log_mgf = kou_log_cf(&Complex::new(1.0, 0.0), lambda, p, eta_up, eta_down).re;
+        let levy = kou_cumulants(lambda, p, eta_up, eta_down).risk_neutral(sigma, jump_log_mgf);
+        let analytic = time_change_cumulants(t, rate, &levy, &TimeChange::none());
+        let numeric = numerical_cumulants(&cf);
+        assert_time_change_cumulants(&analytic, &numeric);
+        //without a time change the fourth cumulant is exact
+        assert_close(analytic.c4, numeric.k4);
+    }
+    #[test]
+    fn time_changed_cumulants_match_differences() {
+        let (t, rate, lambda, mu_l, sig_l, sigma) = (0.7, 0.05, 0.5, -0.1, 0.2, 0.3);
+        let jump_log_mgf = merton_log_cf(&Complex::new(1.0, 0.0), lambda, mu_l, sig_l).re;
+        let levy = merton_cumulants(lambda, mu_l, sig_l).risk_neutral(sigma, jump_log_mgf);
+        //the second speed uses the series for the time change
+        for speed in [0.5, 0.001] {
+            let time_change = TimeChange {
+                sigma,
+                v0: 0.9,
+                speed,
+                eta_v: 0.6,
+                rho: -0.7,
+            };
+            let cf = cf_functions::merton::merton_time_change_cf(
+                t, rate, lambda, mu_l, sig_l, sigma, 0.9, speed, 0.6, -0.7,
+            );
+            assert_time_change_cumulants(
+                &time_change_cumulants(t, rate, &levy, &time_change),
+                &numerical_cumulants(&cf),
+            );
+        }
+    }
+    #[test]
+    fn heston_cumulants_match_differences() {
+        let (t, rate, sigma, v0, speed, eta_v, rho) = (1.5, 0.05, 0.2, 0.06, 1.5, 0.5, -0.7);
+        let cf = cf_functions::gauss::heston_cf(t, rate, sigma, v0, speed, eta_v, rho);
+        let levy = LevyCumulants::default().risk_neutral(sigma, 0.0);
+        let time_change = TimeChange::heston(sigma, v0, speed, eta_v, rho);
+        assert_time_change_cumulants(
+            &time_change_cumulants(t, rate, &levy, &time_change),
+            &numerical_cumulants(&cf),
+        );
+    }
+    #[test]
+    fn bates_cumulants_match_differences() {
+        let (t, rate, lambda, mu_l, sig_l) = (0.7, 0.05, 0.5, -0.1, 0.1);
+        let (sigma, v0, speed, eta_v, rho) = (0.2, 0.04, 1.5, 0.5, -0.5);
+        let heston = HestonParameters {
+            sigma,
+            v0,
+            speed,
+            eta_v,
+            rho,
+        };
+        let cf = bates_cf(t, rate, lambda, mu_l, sig_l, &heston);
+        let diffusion = LevyCumulants::default().risk_neutral(sigma, 0.0);
+        let jump_log_mgf = merton_log_cf(&Complex::new(1.0, 0.0), lambda, mu_l, sig_l).re;
+        let jumps = merton_cumulants(lambda, mu_l, sig_l).risk_neutral(0.0, jump_log_mgf);
+        let analytic = time_change_cumulants(
+            t,
+            rate,
+            &diffusion,
+            &TimeChange::heston(sigma, v0, speed, eta_v, rho),
+        ) + time_change_cumulants(t, 0.0, &jumps, &TimeChange::none());
+        assert_time_change_cumulants(&analytic, &numerical_cumulants(&cf));
+    }
+    #[test]
+    fn range_is_centered_on_the_mean() {
+        let cumulants = Cumulants {
+            c1: 0.1,
+            c2: 0.09,
+            c4: 0.0,
+        };
+        let (lower, upper) = cumulants.range(10.0);
+        assert_abs_diff_eq!(lower, -2.9, epsilon = 0.0000001);
+        assert_abs_diff_eq!(upper, 3.1, epsilon = 0.0000001);
+    }
+    fn leverage_time_change(sigma: f64) -> TimeChange {
+        TimeChange {
+            sigma,
+            v0: 0.9,
+            speed: 0.5,
+            eta_v: 0.3,
+            rho: -0.5,
+        }
+    }
+    #[test]
+    fn kou_cf_is_martingale() {
+        let t = 0.7;
+        let rate = 0.05;
+        let cf = kou_time_change_cf(t, rate, 0.8, 0.3, 8.0, 4.0, &leverage_time_change(0.2));
+        let at_zero = cf(&Complex::new(0.0, 0.0));
+        let at_one = cf(&Complex::new(1.0, 0.0));
+        assert_abs_diff_eq!(at_zero.re, 1.0, epsilon = 0.0000001);
+        assert_abs_diff_eq!(at_one.re, (rate * t).exp(), epsilon = 0.0000001);
+        assert_abs_diff_eq!(at_one.im, 0.0, epsilon = 0.0000001);
+    }
+    #[test]
+    fn vg_cf_is_martingale() {
+        let t = 0.7;
+        let rate = 0.05;
+        let cf = vg_time_change_cf(t, rate, 0.12, 0.2, -0.14, &leverage_time_change(0.1));
+        let at_one = cf(&Complex::new(1.0, 0.0));
+        assert_abs_diff_eq!(at_one.re, (rate * t).exp(), epsilon = 0.0000001);
+        assert_abs_diff_eq!(at_one.im, 0.0, epsilon = 0.0000001);
+    }
+    #[test]
+    fn nig_cf_is_martingale() {
+        let t = 0.7;
+        let rate = 0.05;
+        let cf = nig_time_change_cf(t, rate, 15.0, -5.0, 0.5, &leverage_time_change(0.1));
+        let at_one = cf(&Complex::new(1.0, 0.0));
+        assert_abs_diff_eq!(at_one.re, (rate * t).exp(), epsilon = 0.0000001);
+        assert_abs_diff_eq!(at_one.im, 0.0, epsilon = 0.0000001);
+    }
+    #[test]
+    fn bates_cf_is_martingale() {
+        let t = 0.7;
+        let rate = 0.05;
+        let cf = bates_cf(
+            t,
+            rate,
+            0.5,
+            -0.1,
+            0.1,
+            &HestonParameters {
+                sigma: 0.2,
+                v0: 0.04,
+                speed: 1.5,
+                eta_v: 0.5,
+                rho: -0.5,
+            },
+        );
+        let at_one = cf(&Complex::new(1.0, 0.0));
+        assert_abs_diff_eq!(at_one.re, (rate * t).exp(), epsilon = 0.0000001);
+        assert_abs_diff_eq!(at_one.im, 0.0, epsilon = 0.0000001);
+    }
+    #[test]
+    fn heston_log_cf_dv0_matches_difference() {
+        let u = Complex::new(0.5, -2.0);
+        let h = 0.00001;
+        let numeric = (heston_log_cf(&u, 0.7, 0.2, 0.04 + h, 1.5, 0.5, -0.5)
+            - heston_log_cf(&u, 0.7, 0.2, 0.04 - h, 1.5, 0.5, -0.5))
+            / (2.0 * h);
+        let analytic = heston_log_cf_dv0(&u, 0.7, 0.2, 1.5, 0.5, -0.5);
+        assert_abs_diff_eq!(analytic.re, numeric.re, epsilon = 0.00001);
+        assert_abs_diff_eq!(analytic.im, numeric.im, epsilon = 0.00001);
+    }
+}
@@ -2,22 +2,12 @@ pub const CGMY: i32 = 0;
 pub const MERTON: i32 = 1;
 pub const HESTON: i32 = 2;
 
-pub const PUT_PRICE: i32 = 0;
-pub const CALL_PRICE: i32 = 1;
-
-pub const PUT_DELTA: i32 = 2;
-pub const CALL_DELTA: i32 = 3;
-
-pub const PUT_GAMMA: i32 = 4;
-pub const CALL_GAMMA: i32 = 5;
-
-pub const PUT_THETA: i32 = 6;
-pub const CALL_THETA: i32 = 7;
-
-pub const DENSITY: i32 = 8;
-pub const RISK_MEASURES: i32 = 9;
-
 pub const HESTON_NAME: &str = "heston";
 pub const CGMY_NAME: &str = "cgmy";
 pub const MERTON_NAME: &str = "merton";
 pub const CGMYSE_NAME: &str = "cgmyse";
+pub const KOU_NAME: &str = "kou";
+pub const VG_NAME: &str = "vg";
+pub const NIG_NAME: &str = "nig";
+pub const BATES_NAME: &str = "bates";
+pub const BLACK_SCHOLES_NAME: &str = "blackscholes";
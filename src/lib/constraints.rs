@@ -1,7 +1,12 @@
-use fang_oost_option::option_calibration::OptionDataMaturity;
+use crate::constants::{
+    BATES_NAME, BLACK_SCHOLES_NAME, CGMYSE_NAME, CGMY_NAME, HESTON_NAME, KOU_NAME, MERTON_NAME,
+    NIG_NAME, VG_NAME,
+};
 use rocket::response::Responder;
 use rocket::serde::json::{json, Error as JsonError, Value};
 use rocket::tokio::task::JoinError;
+use rocket::FromForm;
+use serde::de::DeserializeOwned;
 use serde_derive::{Deserialize, Serialize};
 use std::error::Error;
 use std::fmt;
@@ -13,6 +18,10 @@ pub enum ErrorType {
     ValueAtRiskError(String),
     JsonError(String),
     OptimizationError(String),
+    JobError(String),
+    ModelMismatch(String, String),
+    TypeError(String, String),
+    UnsupportedOption(String, String),
 }
 
 #[derive(Debug, PartialEq, Responder, Serialize)]
@@ -32,10 +41,20 @@ impl ParameterError {
                 ErrorType::FunctionError(parameter) => {
                     format!("Function indicator {} does not exist.", parameter)
                 }
-                ErrorType::NoConvergence() => format!("Root does not exist for implied volatility"),
-                ErrorType::ValueAtRiskError(message) => format!("{}", message),
-                ErrorType::JsonError(message) => format!("{}", message),
-                ErrorType::OptimizationError(message)=>format!("{}", message)
+                ErrorType::NoConvergence() => "Root does not exist for implied volatility".to_string(),
+                ErrorType::ValueAtRiskError(message) => message.to_string(),
+                ErrorType::JsonError(message) => message.to_string(),
+                ErrorType::OptimizationError(message) => message.to_string(),
+                ErrorType::JobError(message) => message.to_string(),
+                ErrorType::ModelMismatch(path_model, body_model) => {
+                    format!("Model {} in body does not match model {} in path.", body_model, path_model)
+                }
+                ErrorType::TypeError(parameter, expected) => {
+                    format!("Parameter {} must be a {}.", parameter, expected)
+                }
+                ErrorType::UnsupportedOption(option, sensitivity) => {
+                    format!("Option {} is not supported for {}.", option, sensitivity)
+                }
             }}),
         }
     }
@@ -82,6 +101,7 @@ pub struct ConstraintsSchema<'a> {
     pub description: &'a str,
 }
 #[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CGMYParameters {
     pub c: f64,
     pub g: f64,
@@ -94,6 +114,7 @@ pub struct CGMYParameters {
     pub rho: f64,
 }
 #[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CGMYSEParameters {
     pub c: f64,
     pub g: f64,
@@ -105,6 +126,7 @@ pub struct CGMYSEParameters {
     pub eta_v: f64,
 }
 #[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct MertonParameters {
     pub lambda: f64,
     pub mu_l: f64,
@@ -116,6 +138,61 @@ pub struct MertonParameters {
     pub rho: f64,
 }
 #[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KouParameters {
+    pub lambda: f64,
+    pub p: f64,
+    pub eta_up: f64,
+    pub eta_down: f64,
+    pub sigma: f64,
+    pub v0: f64,
+    pub speed: f64,
+    pub eta_v: f64,
+    pub rho: f64,
+}
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VGParameters {
+    pub sigma_vg: f64,
+    pub nu: f64,
+    pub theta: f64,
+    pub sigma: f64,
+    pub v0: f64,
+    pub speed: f64,
+    pub eta_v: f64,
+    pub rho: f64,
+}
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NIGParameters {
+    pub alpha: f64,
+    pub beta: f64,
+    pub delta: f64,
+    pub sigma: f64,
+    pub v0: f64,
+    pub speed: f64,
+    pub eta_v: f64,
+    pub rho: f64,
+}
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatesParameters {
+    pub lambda: f64,
+    pub mu_l: f64,
+    pub sig_l: f64,
+    pub sigma: f64,
+    pub v0: f64,
+    pub speed: f64,
+    pub eta_v: f64,
+    pub rho: f64,
+}
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlackScholesParameters {
+    pub sigma: f64,
+}
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct HestonParameters {
     pub sigma: f64,
     pub v0: f64,
@@ -124,21 +201,96 @@ pub struct HestonParameters {
     pub rho: f64,
 }
 
+#[derive(Serialize)]
+pub struct ActiveBound {
+    pub parameter: String,
+    pub bound: String, //"lower" or "upper"
+}
+#[derive(Serialize)]
+pub struct CalibrationDiagnostics {
+    pub global_iterations: usize,
+    pub local_iterations: usize,
+    pub cost_history: Vec<f64>,
+    pub active_bounds: Vec<ActiveBound>,
+}
+#[derive(Serialize)]
+pub struct ParameterUncertainty {
+    pub parameter_names: Vec<String>, //row and column order of the matrices
+    pub standard_errors: Vec<f64>,
+    pub covariance: Vec<Vec<f64>>,
+    pub correlation: Vec<Vec<f64>>,
+    pub condition_number: f64, //of the weighted jacobian; null if singular
+}
+#[derive(Serialize)]
+pub struct FittedQuote {
+    pub strike: f64,
+    pub fitted_price: f64,
+    pub market_mid: f64,
+    pub residual: f64, //fitted_price minus market_mid
+}
+#[derive(Serialize)]
+pub struct FittedQuoteMaturity {
+    pub maturity: f64,
+    pub option_data: Vec<FittedQuote>,
+}
 #[derive(Serialize)]
 pub struct CalibrationResponse {
     pub parameters: CFParameters,
     pub final_cost_value: f64,
+    pub fitted_quotes: Vec<FittedQuoteMaturity>,
+    pub diagnostics: CalibrationDiagnostics,
+    pub uncertainty: ParameterUncertainty,
+}
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ZeroRate {
+    pub tenor: f64, //years from now
+    pub rate: f64,  //annualized continuously compounded
+}
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CashDividend {
+    pub time: f64, //years from now
+    pub amount: f64,
+}
+#[derive(Serialize, Deserialize, Default)]
+pub struct OptionQuote {
+    pub strike: f64,
+    pub price: Option<f64>,              //call price
+    pub implied_volatility: Option<f64>, //used when price is not provided
+    pub bid: Option<f64>, //mid of bid and ask is used when neither of the above are provided
+    pub ask: Option<f64>,
+    pub weight: Option<f64>, //defaults to open_interest, or one if neither are provided
+    pub open_interest: Option<f64>,
+}
+#[derive(Serialize, Deserialize)]
+pub struct OptionQuoteMaturity {
+    pub maturity: f64,
+    pub option_data: Vec<OptionQuote>,
+}
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CalibrationObjective {
+    Price,             //root mean squared price error
+    RelativePrice,     //root mean squared price error as a fraction of market price
+    VegaWeighted,      //root mean squared price error divided by Black-Scholes vega
+    ImpliedVolatility, //root mean squared implied volatility error
+    BidAsk,            //root mean squared distance outside of the bid/ask band
 }
 #[derive(Deserialize)]
 pub struct CalibrationParameters {
-    pub option_data: Vec<OptionDataMaturity>,
+    pub option_data: Vec<OptionQuoteMaturity>,
     pub asset: f64,
     pub num_u: usize,
     pub rate: f64,
+    pub dividend_yield: Option<f64>, //continuous, defaults to zero
+    pub borrow_rate: Option<f64>,    //repo or stock loan fee, defaults to zero
+    pub objective: Option<CalibrationObjective>, //defaults to price
+    pub truncation_scale: Option<f64>,
 }
 
 impl CGMYParameters {
-    fn to_vector(&self) -> Vec<(f64, &str)> {
+    pub(crate) fn to_vector(&self) -> Vec<(f64, &str)> {
         vec![
             (self.c, "c"),
             (self.g, "g"),
@@ -151,9 +303,22 @@ impl CGMYParameters {
             (self.rho, "rho"),
         ]
     }
+    pub(crate) fn from_vector(params: &[f64]) -> Self {
+        CGMYParameters {
+            c: params[0],
+            g: params[1],
+            m: params[2],
+            y: params[3],
+            sigma: params[4],
+            v0: params[5],
+            speed: params[6],
+            eta_v: params[7],
+            rho: params[8],
+        }
+    }
 }
 impl CGMYSEParameters {
-    fn to_vector(&self) -> Vec<(f64, &str)> {
+    pub(crate) fn to_vector(&self) -> Vec<(f64, &str)> {
         vec![
             (self.c, "c"),
             (self.g, "g"),
@@ -165,9 +330,21 @@ impl CGMYSEParameters {
             (self.eta_v, "eta_v"),
         ]
     }
+    pub(crate) fn from_vector(params: &[f64]) -> Self {
+        CGMYSEParameters {
+            c: params[0],
+            g: params[1],
+            m: params[2],
+            y: params[3],
+            sigma: params[4],
+            v0: params[5],
+            speed: params[6],
+            eta_v: params[7],
+        }
+    }
 }
 impl HestonParameters {
-    fn to_vector(&self) -> Vec<(f64, &str)> {
+    pub(crate) fn to_vector(&self) -> Vec<(f64, &str)> {
         vec![
             (self.sigma, "sigma"),
             (self.v0, "v0"),
@@ -176,9 +353,127 @@ impl HestonParameters {
             (self.rho, "rho"),
         ]
     }
+    pub(crate) fn from_vector(params: &[f64]) -> Self {
+        HestonParameters {
+            sigma: params[0],
+            v0: params[1],
+            speed: params[2],
+            eta_v: params[3],
+            rho: params[4],
+        }
+    }
 }
 impl MertonParameters {
-    fn to_vector(&self) -> Vec<(f64, &str)> {
+    pub(crate) fn to_vector(&self) -> Vec<(f64, &str)> {
+        vec![
+            (self.lambda, "lambda"),
+            (self.mu_l, "mu_l"),
+            (self.sig_l, "sig_l"),
+            (self.sigma, "sigma"),
+            (self.v0, "v0"),
+            (self.speed, "speed"),
+            (self.eta_v, "eta_v"),
+            (self.rho, "rho"),
+        ]
+    }
+    pub(crate) fn from_vector(params: &[f64]) -> Self {
+        MertonParameters {
+            lambda: params[0],
+            mu_l: params[1],
+            sig_l: params[2],
+            sigma: params[3],
+            v0: params[4],
+            speed: params[5],
+            eta_v: params[6],
+            rho: params[7],
+        }
+    }
+}
+impl KouParameters {
+    pub(crate) fn to_vector(&self) -> Vec<(f64, &str)> {
+        vec![
+            (self.lambda, "lambda"),
+            (self.p, "p"),
+            (self.eta_up, "eta_up"),
+            (self.eta_down, "eta_down"),
+            (self.sigma, "sigma"),
+            (self.v0, "v0"),
+            (self.speed, "speed"),
+            (self.eta_v, "eta_v"),
+            (self.rho, "rho"),
+        ]
+    }
+    pub(crate) fn from_vector(params: &[f64]) -> Self {
+        KouParameters {
+            lambda: params[0],
+            p: params[1],
+            eta_up: params[2],
+            eta_down: params[3],
+            sigma: params[4],
+            v0: params[5],
+            speed: params[6],
+            eta_v: params[7],
+            rho: params[8],
+        }
+    }
+}
+
+impl VGParameters {
+    pub(crate) fn to_vector(&self) -> Vec<(f64, &str)> {
+        vec![
+            (self.sigma_vg, "sigma_vg"),
+            (self.nu, "nu"),
+            (self.theta, "theta"),
+            (self.sigma, "sigma"),
+            (self.v0, "v0"),
+            (self.speed, "speed"),
+            (self.eta_v, "eta_v"),
+            (self.rho, "rho"),
+        ]
+    }
+    pub(crate) fn from_vector(params: &[f64]) -> Self {
+        VGParameters {
+            sigma_vg: params[0],
+            nu: params[1],
+            theta: params[2],
+            sigma: params[3],
+            v0: params[4],
+            speed: params[5],
+            eta_v: params[6],
+            rho: params[7],
+        }
+    }
+}
+
+impl NIGParameters {
+    pub(crate) fn to_vector(&self) -> Vec<(f64, &str)> {
+        vec![
+            (self.alpha, "alpha"),
+            (self.beta, "beta"),
+            (self.delta, "delta"),
+            (self.sigma, "sigma"),
+            (self.v0, "v0"),
+            (self.speed, "speed"),
+            (self.eta_v, "eta_v"),
+            (self.rho, "rho"),
+        ]
+    }
+    pub(crate) fn from_vector(params: &[f64]) -> Self {
+        NIGParameters {
+            alpha: params[0],
+            beta: params[1],
+            delta: params[2],
+            sigma: params[3],
+            v0: params[4],
+            speed: params[5],
+            eta_v: params[6],
+            rho: params[7],
+        }
+    }
+}
+
+impl BatesParameters {
+    pub(crate) fn to_vector(&self) -> Vec<(f64, &str)> {
         vec![
             (self.lambda, "lambda"),
             (self.mu_l, "mu_l"),
@@ -190,37 +485,285 @@ impl MertonParameters {
             (self.rho, "rho"),
         ]
     }
+    pub(crate) fn from_vector(params: &[f64]) -> Self {
+        BatesParameters {
+            lambda: params[0],
+            mu_l: params[1],
+            sig_l: params[2],
+            sigma: params[3],
+            v0: params[4],
+            speed: params[5],
+            eta_v: params[6],
+            rho: params[7],
+        }
+    }
 }
 
+impl BlackScholesParameters {
+    pub(crate) fn to_vector(&self) -> Vec<(f64, &str)> {
+        vec![(self.sigma, "sigma")]
+    }
+    pub(crate) fn from_vector(params: &[f64]) -> Self {
+        BlackScholesParameters { sigma: params[0] }
+    }
+}
+
+/// Tagged by "model", which matches the model names
+/// in `constants`.  Serializing includes the tag, eg
+/// `{"model": "heston", "sigma": 0.2, ...}`, so parameters
+/// serialized by earlier, untagged, versions need a "model"
+/// field (or `parse_model_parameters`) to deserialize.
 #[derive(Serialize, Deserialize)]
-#[serde(untagged)]
+#[serde(tag = "model", rename_all = "lowercase")]
 pub enum CFParameters {
     Merton(MertonParameters),
     CGMY(CGMYParameters),
     CGMYSE(CGMYSEParameters),
     Heston(HestonParameters),
+    Kou(KouParameters),
+    VG(VGParameters),
+    NIG(NIGParameters),
+    Bates(BatesParameters),
+    BlackScholes(BlackScholesParameters),
+}
+
+impl CFParameters {
+    /// Returns parameters of the model named in the path,
+    /// with every value at its lower bound, so that `bounds`
+    /// and `with_vector` apply to the model
+    pub fn from_model(model: &str) -> Result<Self, ParameterError> {
+        let lower = |constraints: Vec<&ConstraintsSchema<'_>>| -> Vec<f64> {
+            constraints
+                .into_iter()
+                .map(|constraint| constraint.lower)
+                .collect()
+        };
+        match model {
+            HESTON_NAME => Ok(CFParameters::Heston(HestonParameters::from_vector(&lower(
+                HESTON_CONSTRAINTS.to_vector(),
+            )))),
+            MERTON_NAME => Ok(CFParameters::Merton(MertonParameters::from_vector(&lower(
+                MERTON_CONSTRAINTS.to_vector(),
+            )))),
+            CGMY_NAME => Ok(CFParameters::CGMY(CGMYParameters::from_vector(&lower(
+                CGMY_CONSTRAINTS.to_vector(),
+            )))),
+            CGMYSE_NAME => Ok(CFParameters::CGMYSE(CGMYSEParameters::from_vector(&lower(
+                CGMYSE_CONSTRAINTS.to_vector(),
+            )))),
+            KOU_NAME => Ok(CFParameters::Kou(KouParameters::from_vector(&lower(
+                KOU_CONSTRAINTS.to_vector(),
+            )))),
+            VG_NAME => Ok(CFParameters::VG(VGParameters::from_vector(&lower(
+                VG_CONSTRAINTS.to_vector(),
+            )))),
+            NIG_NAME => Ok(CFParameters::NIG(NIGParameters::from_vector(&lower(
+                NIG_CONSTRAINTS.to_vector(),
+            )))),
+            BATES_NAME => Ok(CFParameters::Bates(BatesParameters::from_vector(&lower(
+                BATES_CONSTRAINTS.to_vector(),
+            )))),
+            BLACK_SCHOLES_NAME => Ok(CFParameters::BlackScholes(
+                BlackScholesParameters::from_vector(&lower(BLACK_SCHOLES_CONSTRAINTS.to_vector())),
+            )),
+            _ => Err(throw_no_exist_error(model)),
+        }
+    }
+    pub(crate) fn to_vector(&self) -> Vec<(f64, &str)> {
+        match self {
+            CFParameters::Merton(cf_params) => cf_params.to_vector(),
+            CFParameters::CGMY(cf_params) => cf_params.to_vector(),
+            CFParameters::CGMYSE(cf_params) => cf_params.to_vector(),
+            CFParameters::Heston(cf_params) => cf_params.to_vector(),
+            CFParameters::Kou(cf_params) => cf_params.to_vector(),
+            CFParameters::VG(cf_params) => cf_params.to_vector(),
+            CFParameters::NIG(cf_params) => cf_params.to_vector(),
+            CFParameters::Bates(cf_params) => cf_params.to_vector(),
+            CFParameters::BlackScholes(cf_params) => cf_params.to_vector(),
+        }
+    }
+    /// Returns parameters of the same model from values
+    /// ordered as in `to_vector`
+    pub(crate) fn with_vector(&self, params: &[f64]) -> Self {
+        match self {
+            CFParameters::Merton(_) => CFParameters::Merton(MertonParameters::from_vector(params)),
+            CFParameters::CGMY(_) => CFParameters::CGMY(CGMYParameters::from_vector(params)),
+            CFParameters::CGMYSE(_) => CFParameters::CGMYSE(CGMYSEParameters::from_vector(params)),
+            CFParameters::Heston(_) => CFParameters::Heston(HestonParameters::from_vector(params)),
+            CFParameters::Kou(_) => CFParameters::Kou(KouParameters::from_vector(params)),
+            CFParameters::VG(_) => CFParameters::VG(VGParameters::from_vector(params)),
+            CFParameters::NIG(_) => CFParameters::NIG(NIGParameters::from_vector(params)),
+            CFParameters::Bates(_) => CFParameters::Bates(BatesParameters::from_vector(params)),
+            CFParameters::BlackScholes(_) => {
+                CFParameters::BlackScholes(BlackScholesParameters::from_vector(params))
+            }
+        }
+    }
+    /// Returns the lower and upper bounds of each value,
+    /// ordered as in `to_vector`
+    pub(crate) fn bounds(&self) -> Vec<(f64, f64)> {
+        let constraints = match self {
+            CFParameters::Merton(_) => MERTON_CONSTRAINTS.to_vector(),
+            CFParameters::CGMY(_) => CGMY_CONSTRAINTS.to_vector(),
+            CFParameters::CGMYSE(_) => CGMYSE_CONSTRAINTS.to_vector(),
+            CFParameters::Heston(_) => HESTON_CONSTRAINTS.to_vector(),
+            CFParameters::Kou(_) => KOU_CONSTRAINTS.to_vector(),
+            CFParameters::VG(_) => VG_CONSTRAINTS.to_vector(),
+            CFParameters::NIG(_) => NIG_CONSTRAINTS.to_vector(),
+            CFParameters::Bates(_) => BATES_CONSTRAINTS.to_vector(),
+            CFParameters::BlackScholes(_) => BLACK_SCHOLES_CONSTRAINTS.to_vector(),
+        };
+        constraints
+            .into_iter()
+            .map(|constraint| (constraint.lower, constraint.upper))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Precision {
+    Auto,
+}
+
+/// Either the exponent for the number of discrete "u", or
+/// "auto" to refine the grid until prices converge
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum NumU {
+    Exponent(usize),
+    Auto(Precision),
+}
+
+impl NumU {
+    /// Returns the exponent for routes without an auto mode
+    pub fn exponent(&self) -> Result<usize, ParameterError> {
+        match self {
+            NumU::Exponent(num_u) => Ok(*num_u),
+            NumU::Auto(_) => Err(ParameterError::new(&ErrorType::OutOfBounds(
+                "num_u".to_string(),
+            ))),
+        }
+    }
+}
+
+/// Rates and dividends shared by the option and surface
+/// parameters, resolved at each maturity
+#[derive(Serialize, Deserialize)]
+pub struct RateParameters {
+    pub rate: Option<f64>,                 //required unless rate_curve is provided
+    pub rate_curve: Option<Vec<ZeroRate>>, //overrides rate
+    pub dividend_yield: Option<f64>,       //continuous, defaults to zero
+    pub dividend_curve: Option<Vec<ZeroRate>>, //overrides dividend_yield
+    pub borrow_rate: Option<f64>,          //repo or stock loan fee, defaults to zero
+    pub dividends: Option<Vec<CashDividend>>, //paid before expiry, escrowed from the asset
+}
+
+/// Optional query flags of the calculator route
+#[derive(FromForm, Clone, Copy, Debug, Default)]
+pub struct CalculatorOptions {
+    pub include_implied_volatility: Option<bool>,
+    pub strict_implied_volatility: Option<bool>,
+    pub check_arbitrage: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct OptionParameters {
     pub maturity: f64,
-    pub rate: f64,
+    #[serde(flatten)]
+    pub rates: RateParameters,
     pub asset: Option<f64>,
     pub strikes: Option<Vec<f64>>,
     pub quantile: Option<f64>,
-    pub num_u: usize, //raised to the power of two.  if this is 8, then there will be 2^8=256 discrete "u"
+    pub log_returns: Option<Vec<f64>>, //points of the cumulative distribution
+    pub prices: Option<Vec<f64>>,      //used instead of log_returns with the asset
+    pub probabilities: Option<Vec<f64>>, //points of the quantile function
+    pub num_u: NumU, //raised to the power of two.  if this is 8, then there will be 2^8=256 discrete "u"
+    pub tolerance: Option<f64>, //required when num_u is "auto"
+    pub truncation_scale: Option<f64>, //defaults to the route's scale
+    pub cf_parameters: CFParameters,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SurfaceParameters {
+    pub maturities: Vec<f64>,
+    #[serde(flatten)]
+    pub rates: RateParameters,
+    pub asset: f64,
+    pub strikes: Option<Vec<f64>>,
+    pub moneyness: Option<Vec<f64>>, //strike over the forward, used when strikes are not provided
+    pub num_u: usize,
+    pub truncation_scale: Option<f64>,
     pub cf_parameters: CFParameters,
 }
 
 #[derive(Serialize)]
 pub struct ParameterConstraints<'a> {
     pub rate: &'a ConstraintsSchema<'a>,
+    pub dividend_yield: &'a ConstraintsSchema<'a>,
+    pub borrow_rate: &'a ConstraintsSchema<'a>,
     pub asset: &'a ConstraintsSchema<'a>,
     pub maturity: &'a ConstraintsSchema<'a>,
     pub num_u: &'a ConstraintsSchema<'a>,
+    pub tolerance: &'a ConstraintsSchema<'a>,
+    pub truncation_scale: &'a ConstraintsSchema<'a>,
     pub quantile: &'a ConstraintsSchema<'a>,
 }
 
+#[derive(Serialize)]
+pub struct KouConstraints<'a> {
+    pub lambda: &'a ConstraintsSchema<'a>,
+    pub p: &'a ConstraintsSchema<'a>,
+    pub eta_up: &'a ConstraintsSchema<'a>,
+    pub eta_down: &'a ConstraintsSchema<'a>,
+    pub sigma: &'a ConstraintsSchema<'a>,
+    pub v0: &'a ConstraintsSchema<'a>,
+    pub speed: &'a ConstraintsSchema<'a>,
+    pub eta_v: &'a ConstraintsSchema<'a>,
+    pub rho: &'a ConstraintsSchema<'a>,
+}
+
+#[derive(Serialize)]
+pub struct VGConstraints<'a> {
+    pub sigma_vg: &'a ConstraintsSchema<'a>,
+    pub nu: &'a ConstraintsSchema<'a>,
+    pub theta: &'a ConstraintsSchema<'a>,
+    pub sigma: &'a ConstraintsSchema<'a>,
+    pub v0: &'a ConstraintsSchema<'a>,
+    pub speed: &'a ConstraintsSchema<'a>,
+    pub eta_v: &'a ConstraintsSchema<'a>,
+    pub rho: &'a ConstraintsSchema<'a>,
+}
+
+#[derive(Serialize)]
+pub struct NIGConstraints<'a> {
+    pub alpha: &'a ConstraintsSchema<'a>,
+    pub beta: &'a ConstraintsSchema<'a>,
+    pub delta: &'a ConstraintsSchema<'a>,
+    pub sigma: &'a ConstraintsSchema<'a>,
+    pub v0: &'a ConstraintsSchema<'a>,
+    pub speed: &'a ConstraintsSchema<'a>,
+    pub eta_v: &'a ConstraintsSchema<'a>,
+    pub rho: &'a ConstraintsSchema<'a>,
+}
+
+#[derive(Serialize)]
+pub struct BatesConstraints<'a> {
+    pub lambda: &'a ConstraintsSchema<'a>,
+    pub mu_l: &'a ConstraintsSchema<'a>,
+    pub sig_l: &'a ConstraintsSchema<'a>,
+    pub sigma: &'a ConstraintsSchema<'a>,
+    pub v0: &'a ConstraintsSchema<'a>,
+    pub speed: &'a ConstraintsSchema<'a>,
+    pub eta_v: &'a ConstraintsSchema<'a>,
+    pub rho: &'a ConstraintsSchema<'a>,
+}
+
+#[derive(Serialize)]
+pub struct BlackScholesConstraints<'a> {
+    pub sigma: &'a ConstraintsSchema<'a>,
+}
+
 #[derive(Serialize)]
 pub struct MertonConstraints<'a> {
     pub lambda: &'a ConstraintsSchema<'a>,
@@ -268,7 +811,7 @@ pub struct HestonConstraints<'a> {
 }
 
 impl CGMYConstraints<'_> {
-    pub fn to_vector(&self) -> Vec<&ConstraintsSchema> {
+    pub fn to_vector(&self) -> Vec<&ConstraintsSchema<'_>> {
         vec![
             &self.c,
             &self.g,
@@ -283,7 +826,7 @@ impl CGMYConstraints<'_> {
     }
 }
 impl CGMYSEConstraints<'_> {
-    pub fn to_vector(&self) -> Vec<&ConstraintsSchema> {
+    pub fn to_vector(&self) -> Vec<&ConstraintsSchema<'_>> {
         vec![
             &self.c,
             &self.g,
@@ -297,12 +840,72 @@ impl CGMYSEConstraints<'_> {
     }
 }
 impl HestonConstraints<'_> {
-    pub fn to_vector(&self) -> Vec<&ConstraintsSchema> {
+    pub fn to_vector(&self) -> Vec<&ConstraintsSchema<'_>> {
         vec![&self.sigma, &self.v0, &self.speed, &self.eta_v, &self.rho]
     }
 }
 impl MertonConstraints<'_> {
-    pub fn to_vector(&self) -> Vec<&ConstraintsSchema> {
+    pub fn to_vector(&self) -> Vec<&ConstraintsSchema<'_>> {
+        vec![
+            &self.lambda,
+            &self.mu_l,
+            &self.sig_l,
+            &self.sigma,
+            &self.v0,
+            &self.speed,
+            &self.eta_v,
+            &self.rho,
+        ]
+    }
+}
+impl KouConstraints<'_> {
+    pub fn to_vector(&self) -> Vec<&ConstraintsSchema<'_>> {
+        vec![
+            &self.lambda,
+            &self.p,
+            &self.eta_up,
+            &self.eta_down,
+            &self.sigma,
+            &self.v0,
+            &self.speed,
+            &self.eta_v,
+            &self.rho,
+        ]
+    }
+}
+
+impl VGConstraints<'_> {
+    pub fn to_vector(&self) -> Vec<&ConstraintsSchema<'_>> {
+        vec![
+            &self.sigma_vg,
+            &self.nu,
+            &self.theta,
+            &self.sigma,
+            &self.v0,
+            &self.speed,
+            &self.eta_v,
+            &self.rho,
+        ]
+    }
+}
+
+impl NIGConstraints<'_> {
+    pub fn to_vector(&self) -> Vec<&ConstraintsSchema<'_>> {
+        vec![
+            &self.alpha,
+            &self.beta,
+            &self.delta,
+            &self.sigma,
+            &self.v0,
+            &self.speed,
+            &self.eta_v,
+            &self.rho,
+        ]
+    }
+}
+
+impl BatesConstraints<'_> {
+    pub fn to_vector(&self) -> Vec<&ConstraintsSchema<'_>> {
         vec![
             &self.lambda,
             &self.mu_l,
@@ -316,6 +919,12 @@ impl MertonConstraints<'_> {
     }
 }
 
+impl BlackScholesConstraints<'_> {
+    pub fn to_vector(&self) -> Vec<&ConstraintsSchema<'_>> {
+        vec![&self.sigma]
+    }
+}
+
 pub const PARAMETER_CONSTRAINTS: ParameterConstraints = ParameterConstraints {
     rate: &ConstraintsSchema {
         lower: 0.0,
@@ -323,6 +932,18 @@ pub const PARAMETER_CONSTRAINTS: ParameterConstraints = ParameterConstraints {
         types: "float",
         description: "Annualized risk-free interest rate",
     },
+    dividend_yield: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 0.4,
+        types: "float",
+        description: "Annualized continuous dividend yield of the underlying asset",
+    },
+    borrow_rate: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 0.4,
+        types: "float",
+        description: "Annualized cost of borrowing the underlying asset, reduces the forward like a dividend yield",
+    },
     asset: &ConstraintsSchema {
         lower: 0.0,
         upper: 1000000.0,
@@ -341,6 +962,18 @@ pub const PARAMETER_CONSTRAINTS: ParameterConstraints = ParameterConstraints {
         types: "int",
         description: "Exponent for the precision of the numeric inversion.  For example, 8 represents 2^8=256."
     },
+    tolerance: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 1.0,
+        types: "float",
+        description: "Largest change in value between successive doublings of \"u\" when num_u is \"auto\"."
+    },
+    truncation_scale: &ConstraintsSchema {
+        lower: 1.0,
+        upper: 20.0,
+        types: "float",
+        description: "Multiple of sqrt(c2+sqrt(c4)), from the cumulants of the log return, used for the truncation range.  Defaults to 10 for options and 5 for densities and risk metrics."
+    },
     quantile: &ConstraintsSchema {
         lower: 0.0,
         upper: 1.0,
@@ -400,6 +1033,226 @@ pub const MERTON_CONSTRAINTS: MertonConstraints = MertonConstraints {
     },
 };
 
+pub const KOU_CONSTRAINTS: KouConstraints = KouConstraints {
+    lambda: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 2.0,
+        types: "float",
+        description: "Annualized frequency of jumps for the asset process",
+    },
+    p: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 1.0,
+        types: "float",
+        description: "Probability that a jump is upward",
+    },
+    eta_up: &ConstraintsSchema {
+        lower: 1.1,
+        upper: 50.0,
+        types: "float",
+        description: "Rate of the exponential upward jump size; mean upward jump is 1/eta_up",
+    },
+    eta_down: &ConstraintsSchema {
+        lower: 0.1,
+        upper: 50.0,
+        types: "float",
+        description: "Rate of the exponential downward jump size; mean downward jump is 1/eta_down",
+    },
+    sigma: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 1.0,
+        types: "float",
+        description: "Volatility of diffusion component of asset process",
+    },
+    v0: &ConstraintsSchema {
+        lower: 0.2,
+        upper: 1.8,
+        types: "float",
+        description: "Initial value of the time-change diffusion",
+    },
+    speed: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 3.0,
+        types: "float",
+        description: "Rate at which time-change diffusion reverts to mean",
+    },
+    eta_v: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 3.0,
+        types: "float",
+        description: "Volatility of time-change diffusion",
+    },
+    rho: &ConstraintsSchema {
+        lower: -1.0,
+        upper: 1.0,
+        types: "float",
+        description: "Correlation between asset and time-change diffusions",
+    },
+};
+
+pub const VG_CONSTRAINTS: VGConstraints = VGConstraints {
+    sigma_vg: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 1.0,
+        types: "float",
+        description: "Volatility of the Brownian motion subordinated by the gamma process",
+    },
+    nu: &ConstraintsSchema {
+        lower: 0.01,
+        upper: 2.0,
+        types: "float",
+        description: "Variance rate of the gamma time change, controls kurtosis",
+    },
+    theta: &ConstraintsSchema {
+        lower: -1.0,
+        upper: 1.0,
+        types: "float",
+        description:
+            "Drift of the Brownian motion subordinated by the gamma process, controls skew",
+    },
+    sigma: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 1.0,
+        types: "float",
+        description: "Volatility of diffusion component of asset process",
+    },
+    v0: &ConstraintsSchema {
+        lower: 0.2,
+        upper: 1.8,
+        types: "float",
+        description: "Initial value of the time-change diffusion",
+    },
+    speed: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 3.0,
+        types: "float",
+        description: "Rate at which time-change diffusion reverts to mean",
+    },
+    eta_v: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 3.0,
+        types: "float",
+        description: "Volatility of time-change diffusion",
+    },
+    rho: &ConstraintsSchema {
+        lower: -1.0,
+        upper: 1.0,
+        types: "float",
+        description: "Correlation between asset and time-change diffusions",
+    },
+};
+
+pub const NIG_CONSTRAINTS: NIGConstraints = NIGConstraints {
+    alpha: &ConstraintsSchema {
+        lower: 0.1,
+        upper: 50.0,
+        types: "float",
+        description: "Tail heaviness of the normal inverse Gaussian distribution",
+    },
+    beta: &ConstraintsSchema {
+        lower: -50.0,
+        upper: 50.0,
+        types: "float",
+        description: "Asymmetry of the normal inverse Gaussian distribution, must be less than alpha in absolute value",
+    },
+    delta: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 5.0,
+        types: "float",
+        description: "Scale of the normal inverse Gaussian distribution",
+    },
+    sigma: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 1.0,
+        types: "float",
+        description: "Volatility of diffusion component of asset process",
+    },
+    v0: &ConstraintsSchema {
+        lower: 0.2,
+        upper: 1.8,
+        types: "float",
+        description: "Initial value of the time-change diffusion",
+    },
+    speed: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 3.0,
+        types: "float",
+        description: "Rate at which time-change diffusion reverts to mean",
+    },
+    eta_v: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 3.0,
+        types: "float",
+        description: "Volatility of time-change diffusion",
+    },
+    rho: &ConstraintsSchema {
+        lower: -1.0,
+        upper: 1.0,
+        types: "float",
+        description: "Correlation between asset and time-change diffusions",
+    },
+};
+
+pub const BATES_CONSTRAINTS: BatesConstraints = BatesConstraints {
+    lambda: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 2.0,
+        types: "float",
+        description: "Annualized frequency of jumps for the asset process",
+    },
+    mu_l: &ConstraintsSchema {
+        lower: -1.0,
+        upper: 1.0,
+        types: "float",
+        description: "Mean jump size",
+    },
+    sig_l: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 2.0,
+        types: "float",
+        description: "Volatility of jump size",
+    },
+    sigma: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 1.0,
+        types: "float",
+        description: "Square root of mean of variance process",
+    },
+    v0: &ConstraintsSchema {
+        lower: 0.001,
+        upper: 1.5,
+        types: "float",
+        description: "Square root of initial value of the instantaneous variance",
+    },
+    speed: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 3.0,
+        types: "float",
+        description: "Rate at which variance reverts to mean",
+    },
+    eta_v: &ConstraintsSchema {
+        lower: 0.0,
+        upper: 3.0,
+        types: "float",
+        description: "Vol of vol: volatility of instantaneous variance",
+    },
+    rho: &ConstraintsSchema {
+        lower: -1.0,
+        upper: 1.0,
+        types: "float",
+        description: "Correlation between asset and variance diffusions",
+    },
+};
+
+pub const BLACK_SCHOLES_CONSTRAINTS: BlackScholesConstraints = BlackScholesConstraints {
+    sigma: &ConstraintsSchema {
+        lower: 0.001,
+        upper: 2.0,
+        types: "float",
+        description: "Volatility of the asset process",
+    },
+};
+
 pub const CGMY_CONSTRAINTS: CGMYConstraints = CGMYConstraints {
     c: &ConstraintsSchema {
         lower: 0.0,
@@ -558,65 +1411,309 @@ fn check_constraint<'a>(
         )))
     }
 }
-fn check_constraint_option<'a>(
-    parameter: &Option<f64>,
-    constraint: &'a ConstraintsSchema,
-    parameter_name: &'a str,
+fn check_constraint_option<'a>(
+    parameter: &Option<f64>,
+    constraint: &'a ConstraintsSchema,
+    parameter_name: &'a str,
+) -> Result<(), ParameterError> {
+    match parameter {
+        Some(param) => check_constraint(*param, constraint, parameter_name),
+        None => Ok(()),
+    }
+}
+
+/// Curves need at least one point, and strictly
+/// increasing tenors so that they can be interpolated
+fn check_curve(
+    curve: &Option<Vec<ZeroRate>>,
+    constraint: &ConstraintsSchema,
+    curve_name: &str,
+) -> Result<(), ParameterError> {
+    let curve = match curve {
+        Some(curve) => curve,
+        None => return Ok(()),
+    };
+    if curve.is_empty() {
+        return Err(throw_no_exist_error(curve_name));
+    }
+    for ZeroRate { tenor, rate } in curve.iter() {
+        check_constraint(*tenor, PARAMETER_CONSTRAINTS.maturity, curve_name)?;
+        check_constraint(*rate, constraint, curve_name)?;
+    }
+    if curve.windows(2).any(|pair| pair[0].tenor >= pair[1].tenor) {
+        return Err(ParameterError::new(&ErrorType::OutOfBounds(
+            curve_name.to_string(),
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the rates and dividends shared by the option
+/// and surface parameters
+fn check_rate_parameters(
+    parameters: &RateParameters,
+    constraints: &ParameterConstraints,
+) -> Result<(), ParameterError> {
+    let RateParameters {
+        rate,
+        rate_curve,
+        dividend_yield,
+        dividend_curve,
+        borrow_rate,
+        dividends,
+    } = parameters;
+    if rate.is_none() && rate_curve.is_none() {
+        return Err(throw_no_exist_error("rate"));
+    }
+    check_constraint_option(rate, constraints.rate, "rate")?;
+    check_curve(rate_curve, constraints.rate, "rate_curve")?;
+    check_constraint_option(dividend_yield, constraints.dividend_yield, "dividend_yield")?;
+    check_curve(dividend_curve, constraints.dividend_yield, "dividend_curve")?;
+    check_constraint_option(borrow_rate, constraints.borrow_rate, "borrow_rate")?;
+    for CashDividend { time, amount } in dividends.iter().flatten() {
+        check_constraint(*time, constraints.maturity, "dividends")?;
+        check_constraint(*amount, constraints.asset, "dividends")?;
+    }
+    Ok(())
+}
+
+pub fn check_parameters(
+    parameters: &OptionParameters,
+    constraints: &ParameterConstraints,
+) -> Result<(), ParameterError> {
+    check_constraint_option(&parameters.asset, constraints.asset, "asset")?;
+    check_constraint(parameters.maturity, constraints.maturity, "maturity")?;
+    check_rate_parameters(&parameters.rates, constraints)?;
+    match parameters.num_u {
+        NumU::Exponent(num_u) => check_constraint(num_u as f64, constraints.num_u, "num_u")?,
+        NumU::Auto(_) if parameters.tolerance.is_none() => {
+            return Err(throw_no_exist_error("tolerance"))
+        }
+        NumU::Auto(_) => {}
+    }
+    check_constraint_option(&parameters.tolerance, constraints.tolerance, "tolerance")?;
+    check_constraint_option(
+        &parameters.truncation_scale,
+        constraints.truncation_scale,
+        "truncation_scale",
+    )?;
+    check_constraint_option(&parameters.quantile, constraints.quantile, "quantile")?;
+    for price in parameters.prices.iter().flatten() {
+        check_constraint(*price, constraints.asset, "prices")?;
+    }
+    //the quantile is unbounded at zero and one
+    for probability in parameters.probabilities.iter().flatten() {
+        if *probability <= constraints.quantile.lower || *probability >= constraints.quantile.upper
+        {
+            return Err(ParameterError::new(&ErrorType::OutOfBounds(
+                "probabilities".to_string(),
+            )));
+        }
+    }
+    Ok(())
+}
+pub fn check_surface_parameters(
+    parameters: &SurfaceParameters,
+    constraints: &ParameterConstraints,
 ) -> Result<(), ParameterError> {
-    match parameter {
-        Some(param) => check_constraint(*param, constraint, parameter_name),
-        None => Ok(()),
+    check_constraint(parameters.asset, constraints.asset, "asset")?;
+    if parameters.maturities.is_empty() {
+        return Err(throw_no_exist_error("maturities"));
+    }
+    for maturity in parameters.maturities.iter() {
+        check_constraint(*maturity, constraints.maturity, "maturities")?;
     }
+    check_rate_parameters(&parameters.rates, constraints)?;
+    if parameters.strikes.is_none() && parameters.moneyness.is_none() {
+        return Err(throw_no_exist_error("strikes"));
+    }
+    check_constraint(parameters.num_u as f64, constraints.num_u, "num_u")?;
+    check_constraint_option(
+        &parameters.truncation_scale,
+        constraints.truncation_scale,
+        "truncation_scale",
+    )?;
+    Ok(())
 }
-
-pub fn check_parameters<'a>(
-    parameters: &OptionParameters,
+pub fn check_calibration_parameters(
+    parameters: &CalibrationParameters,
     constraints: &ParameterConstraints,
 ) -> Result<(), ParameterError> {
-    check_constraint_option(&parameters.asset, &constraints.asset, "asset")?;
-    check_constraint(parameters.maturity, &constraints.maturity, "maturity")?;
-    check_constraint(parameters.rate, &constraints.rate, "rate")?;
-    check_constraint(parameters.num_u as f64, &constraints.num_u, "num_u")?;
-    check_constraint_option(&parameters.quantile, &constraints.quantile, "quantile")?;
+    check_constraint(parameters.asset, constraints.asset, "asset")?;
+    check_constraint(parameters.rate, constraints.rate, "rate")?;
+    check_constraint_option(
+        &parameters.dividend_yield,
+        constraints.dividend_yield,
+        "dividend_yield",
+    )?;
+    check_constraint_option(
+        &parameters.borrow_rate,
+        constraints.borrow_rate,
+        "borrow_rate",
+    )?;
+    check_constraint(parameters.num_u as f64, constraints.num_u, "num_u")?;
+    check_constraint_option(
+        &parameters.truncation_scale,
+        constraints.truncation_scale,
+        "truncation_scale",
+    )?;
+    for OptionQuoteMaturity { maturity, .. } in parameters.option_data.iter() {
+        check_constraint(*maturity, constraints.maturity, "maturity")?;
+    }
     Ok(())
 }
-pub fn check_heston_parameters<'a>(
+pub fn check_heston_parameters(
     parameters: &HestonParameters,
     constraints: &HestonConstraints,
 ) -> Result<(), ParameterError> {
     for ((param, name), constraint) in parameters.to_vector().iter().zip(constraints.to_vector()) {
-        check_constraint(*param, &constraint, name)?;
+        check_constraint(*param, constraint, name)?;
     }
     Ok(())
 }
-pub fn check_merton_parameters<'a>(
+pub fn check_merton_parameters(
     parameters: &MertonParameters,
     constraints: &MertonConstraints,
 ) -> Result<(), ParameterError> {
     for ((param, name), constraint) in parameters.to_vector().iter().zip(constraints.to_vector()) {
-        check_constraint(*param, &constraint, name)?;
+        check_constraint(*param, constraint, name)?;
+    }
+    Ok(())
+}
+pub fn check_kou_parameters(
+    parameters: &KouParameters,
+    constraints: &KouConstraints,
+) -> Result<(), ParameterError> {
+    for ((param, name), constraint) in parameters.to_vector().iter().zip(constraints.to_vector()) {
+        check_constraint(*param, constraint, name)?;
+    }
+    Ok(())
+}
+pub fn check_vg_parameters(
+    parameters: &VGParameters,
+    constraints: &VGConstraints,
+) -> Result<(), ParameterError> {
+    for ((param, name), constraint) in parameters.to_vector().iter().zip(constraints.to_vector()) {
+        check_constraint(*param, constraint, name)?;
+    }
+    //the exponential moment of the jumps has to exist
+    if 1.0 - parameters.theta * parameters.nu - 0.5 * parameters.sigma_vg.powi(2) * parameters.nu
+        <= 0.0
+    {
+        return Err(ParameterError::new(&ErrorType::OutOfBounds(
+            "nu".to_string(),
+        )));
+    }
+    Ok(())
+}
+pub fn check_nig_parameters(
+    parameters: &NIGParameters,
+    constraints: &NIGConstraints,
+) -> Result<(), ParameterError> {
+    for ((param, name), constraint) in parameters.to_vector().iter().zip(constraints.to_vector()) {
+        check_constraint(*param, constraint, name)?;
+    }
+    //the exponential moment of the jumps has to exist
+    if parameters.alpha <= parameters.beta.abs()
+        || parameters.alpha <= (parameters.beta + 1.0).abs()
+    {
+        return Err(ParameterError::new(&ErrorType::OutOfBounds(
+            "beta".to_string(),
+        )));
+    }
+    Ok(())
+}
+pub fn check_bates_parameters(
+    parameters: &BatesParameters,
+    constraints: &BatesConstraints,
+) -> Result<(), ParameterError> {
+    for ((param, name), constraint) in parameters.to_vector().iter().zip(constraints.to_vector()) {
+        check_constraint(*param, constraint, name)?;
+    }
+    Ok(())
+}
+pub fn check_black_scholes_parameters(
+    parameters: &BlackScholesParameters,
+    constraints: &BlackScholesConstraints,
+) -> Result<(), ParameterError> {
+    for ((param, name), constraint) in parameters.to_vector().iter().zip(constraints.to_vector()) {
+        check_constraint(*param, constraint, name)?;
     }
     Ok(())
 }
-pub fn check_cgmy_parameters<'a>(
+pub fn check_cgmy_parameters(
     parameters: &CGMYParameters,
     constraints: &CGMYConstraints,
 ) -> Result<(), ParameterError> {
     for ((param, name), constraint) in parameters.to_vector().iter().zip(constraints.to_vector()) {
-        check_constraint(*param, &constraint, name)?;
+        check_constraint(*param, constraint, name)?;
     }
     Ok(())
 }
-pub fn check_cgmyse_parameters<'a>(
+pub fn check_cgmyse_parameters(
     parameters: &CGMYSEParameters,
     constraints: &CGMYSEConstraints,
 ) -> Result<(), ParameterError> {
     for ((param, name), constraint) in parameters.to_vector().iter().zip(constraints.to_vector()) {
-        check_constraint(*param, &constraint, name)?;
+        check_constraint(*param, constraint, name)?;
     }
     Ok(())
 }
 
+/// Deserializes a request body whose "cf_parameters" are
+/// for the model in the path.  The path model is used as
+/// the "model" tag when the body has none; a body tagged
+/// with a different model is rejected.
+/// # Examples
+///
+/// ```
+/// extern crate rocket;
+/// extern crate utils;
+/// use rocket::serde::json::json;
+/// use utils::constraints;
+/// # fn main() {
+/// let body = json!({
+///     "maturity": 1.0,
+///     "rate": 0.05,
+///     "num_u": 8,
+///     "cf_parameters": {
+///         "sigma": 0.3, "v0": 0.2, "speed": 0.5, "eta_v": 0.3, "rho": -0.5
+///     }
+/// });
+/// let parameters: constraints::OptionParameters =
+///     constraints::parse_model_parameters("heston", body).unwrap();
+/// # }
+/// ```
+pub fn parse_model_parameters<T: DeserializeOwned>(
+    model: &str,
+    mut parameters: Value,
+) -> Result<T, ParameterError> {
+    let cf_parameters = parameters
+        .get_mut("cf_parameters")
+        .and_then(|cf_parameters| cf_parameters.as_object_mut())
+        .ok_or_else(|| throw_no_exist_error("cf_parameters"))?;
+    match cf_parameters.get("model") {
+        Some(Value::String(body_model)) if body_model != model => {
+            return Err(ParameterError::new(&ErrorType::ModelMismatch(
+                model.to_string(),
+                body_model.to_string(),
+            )));
+        }
+        Some(Value::String(_)) => {}
+        Some(_) => {
+            return Err(ParameterError::new(&ErrorType::TypeError(
+                "model".to_string(),
+                "string".to_string(),
+            )))
+        }
+        None => {
+            cf_parameters.insert("model".to_string(), Value::String(model.to_string()));
+        }
+    }
+    serde_json::from_value(parameters)
+        .map_err(|err| ParameterError::new(&ErrorType::JsonError(err.to_string())))
+}
+
 pub fn throw_no_exist_error(parameter: &str) -> ParameterError {
     ParameterError::new(&ErrorType::NoExist(parameter.to_string()))
 }
@@ -683,11 +1780,23 @@ mod tests {
     #[test]
     fn test_check_parameters_ok() {
         let parameters = OptionParameters {
-            rate: 0.05,
+            rates: RateParameters {
+                rate: Some(0.05),
+                rate_curve: None,
+                dividend_yield: Some(0.02),
+                dividend_curve: None,
+                borrow_rate: None,
+                dividends: None,
+            },
             asset: Some(50.0),
             strikes: None,
             quantile: None,
-            num_u: 8,
+            log_returns: None,
+            prices: None,
+            probabilities: None,
+            num_u: NumU::Exponent(8),
+            tolerance: None,
+            truncation_scale: None,
             maturity: 1.0,
             cf_parameters: CFParameters::Heston(HestonParameters {
                 sigma: 0.3,
@@ -701,14 +1810,58 @@ mod tests {
         assert!(result.is_ok());
     }
     #[test]
+    fn test_check_parameters_probabilities_err() {
+        for probability in [0.0, 1.0, 1.5].iter() {
+            let parameters = OptionParameters {
+                rates: RateParameters {
+                    rate: Some(0.05),
+                    rate_curve: None,
+                    dividend_yield: None,
+                    dividend_curve: None,
+                    borrow_rate: None,
+                    dividends: None,
+                },
+                asset: Some(50.0),
+                strikes: None,
+                quantile: None,
+                log_returns: None,
+                prices: None,
+                probabilities: Some(vec![0.5, *probability]),
+                num_u: NumU::Exponent(8),
+                tolerance: None,
+                truncation_scale: None,
+                maturity: 1.0,
+                cf_parameters: CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+            };
+            assert_eq!(
+                check_parameters(&parameters, &PARAMETER_CONSTRAINTS)
+                    .unwrap_err()
+                    .to_string(),
+                "Parameter probabilities out of bounds."
+            );
+        }
+    }
+    #[test]
     fn test_check_parameters_err() {
         let parameters = OptionParameters {
-            rate: -0.05,
+            rates: RateParameters {
+                rate: Some(-0.05),
+                rate_curve: None,
+                dividend_yield: None,
+                dividend_curve: None,
+                borrow_rate: None,
+                dividends: None,
+            },
             asset: Some(50.0),
             strikes: None,
             quantile: None,
+            log_returns: None,
+            prices: None,
+            probabilities: None,
             maturity: 1.0,
-            num_u: 8,
+            num_u: NumU::Exponent(8),
+            tolerance: None,
+            truncation_scale: None,
             cf_parameters: CFParameters::Heston(HestonParameters {
                 sigma: 0.3,
                 v0: 0.2,
@@ -725,6 +1878,187 @@ mod tests {
         );
     }
     #[test]
+    fn test_check_parameters_dividend_yield_err() {
+        let parameters = OptionParameters {
+            rates: RateParameters {
+                rate: Some(0.05),
+                rate_curve: None,
+                dividend_yield: Some(-0.02),
+                dividend_curve: None,
+                borrow_rate: None,
+                dividends: None,
+            },
+            asset: Some(50.0),
+            strikes: None,
+            quantile: None,
+            log_returns: None,
+            prices: None,
+            probabilities: None,
+            maturity: 1.0,
+            num_u: NumU::Exponent(8),
+            tolerance: None,
+            truncation_scale: None,
+            cf_parameters: CFParameters::Heston(HestonParameters {
+                sigma: 0.3,
+                v0: 0.2,
+                speed: 0.5,
+                eta_v: 0.3,
+                rho: -0.2,
+            }),
+        };
+        let result = check_parameters(&parameters, &PARAMETER_CONSTRAINTS);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Parameter dividend_yield out of bounds."
+        );
+    }
+    #[test]
+    fn test_check_parameters_dividends_err() {
+        let parameters = OptionParameters {
+            rates: RateParameters {
+                rate: Some(0.05),
+                rate_curve: None,
+                dividend_yield: None,
+                dividend_curve: None,
+                borrow_rate: None,
+                dividends: Some(vec![CashDividend {
+                    time: 0.5,
+                    amount: -1.0,
+                }]),
+            },
+            asset: Some(50.0),
+            strikes: None,
+            quantile: None,
+            log_returns: None,
+            prices: None,
+            probabilities: None,
+            maturity: 1.0,
+            num_u: NumU::Exponent(8),
+            tolerance: None,
+            truncation_scale: None,
+            cf_parameters: CFParameters::Heston(HestonParameters {
+                sigma: 0.3,
+                v0: 0.2,
+                speed: 0.5,
+                eta_v: 0.3,
+                rho: -0.2,
+            }),
+        };
+        let result = check_parameters(&parameters, &PARAMETER_CONSTRAINTS);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Parameter dividends out of bounds."
+        );
+    }
+    #[test]
+    fn test_check_parameters_rate_curve_err() {
+        let parameters = OptionParameters {
+            rates: RateParameters {
+                rate: None,
+                rate_curve: Some(vec![
+                    ZeroRate {
+                        tenor: 1.0,
+                        rate: 0.02,
+                    },
+                    ZeroRate {
+                        tenor: 0.5,
+                        rate: 0.01,
+                    },
+                ]),
+                dividend_yield: None,
+                dividend_curve: None,
+                borrow_rate: None,
+                dividends: None,
+            },
+            asset: Some(50.0),
+            strikes: None,
+            quantile: None,
+            log_returns: None,
+            prices: None,
+            probabilities: None,
+            maturity: 1.0,
+            num_u: NumU::Exponent(8),
+            tolerance: None,
+            truncation_scale: None,
+            cf_parameters: CFParameters::Heston(HestonParameters {
+                sigma: 0.3,
+                v0: 0.2,
+                speed: 0.5,
+                eta_v: 0.3,
+                rho: -0.2,
+            }),
+        };
+        let result = check_parameters(&parameters, &PARAMETER_CONSTRAINTS);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Parameter rate_curve out of bounds."
+        );
+    }
+    #[test]
+    fn test_check_parameters_no_rate_err() {
+        let parameters = OptionParameters {
+            rates: RateParameters {
+                rate: None,
+                rate_curve: None,
+                dividend_yield: None,
+                dividend_curve: None,
+                borrow_rate: None,
+                dividends: None,
+            },
+            asset: Some(50.0),
+            strikes: None,
+            quantile: None,
+            log_returns: None,
+            prices: None,
+            probabilities: None,
+            maturity: 1.0,
+            num_u: NumU::Exponent(8),
+            tolerance: None,
+            truncation_scale: None,
+            cf_parameters: CFParameters::Heston(HestonParameters {
+                sigma: 0.3,
+                v0: 0.2,
+                speed: 0.5,
+                eta_v: 0.3,
+                rho: -0.2,
+            }),
+        };
+        let result = check_parameters(&parameters, &PARAMETER_CONSTRAINTS);
+        assert_eq!(result.unwrap_err(), throw_no_exist_error("rate"));
+    }
+    #[test]
+    fn test_check_surface_parameters() {
+        let mut parameters = SurfaceParameters {
+            maturities: vec![0.5, 1.0],
+            rates: RateParameters {
+                rate: Some(0.03),
+                rate_curve: None,
+                dividend_yield: None,
+                dividend_curve: None,
+                borrow_rate: None,
+                dividends: None,
+            },
+            asset: 50.0,
+            strikes: None,
+            moneyness: Some(vec![0.9, 1.0, 1.1]),
+            num_u: 8,
+            truncation_scale: None,
+            cf_parameters: CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+        };
+        assert!(check_surface_parameters(&parameters, &PARAMETER_CONSTRAINTS).is_ok());
+        parameters.moneyness = None;
+        assert_eq!(
+            check_surface_parameters(&parameters, &PARAMETER_CONSTRAINTS).unwrap_err(),
+            throw_no_exist_error("strikes")
+        );
+        parameters.strikes = Some(vec![50.0]);
+        parameters.maturities = vec![];
+        assert_eq!(
+            check_surface_parameters(&parameters, &PARAMETER_CONSTRAINTS).unwrap_err(),
+            throw_no_exist_error("maturities")
+        );
+    }
+    #[test]
     fn test_check_heston_parameters_ok() {
         let parameters = HestonParameters {
             sigma: 0.3,
@@ -787,6 +2121,110 @@ mod tests {
         );
     }
     #[test]
+    fn test_check_kou_parameters_ok() {
+        let parameters = KouParameters {
+            lambda: 0.5,
+            p: 0.3,
+            eta_up: 10.0,
+            eta_down: 5.0,
+            sigma: 0.2,
+            v0: 0.9,
+            speed: 0.5,
+            eta_v: 0.3,
+            rho: -0.2,
+        };
+        let result = check_kou_parameters(&parameters, &KOU_CONSTRAINTS);
+        assert!(result.is_ok());
+    }
+    #[test]
+    fn test_check_kou_parameters_err() {
+        //upward jumps without a finite mean of the exponential
+        let parameters = KouParameters {
+            lambda: 0.5,
+            p: 0.3,
+            eta_up: 0.9,
+            eta_down: 5.0,
+            sigma: 0.2,
+            v0: 0.9,
+            speed: 0.5,
+            eta_v: 0.3,
+            rho: -0.2,
+        };
+        let result = check_kou_parameters(&parameters, &KOU_CONSTRAINTS);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Parameter eta_up out of bounds."
+        );
+    }
+    #[test]
+    fn test_check_vg_parameters_ok() {
+        let parameters = VGParameters {
+            sigma_vg: 0.12,
+            nu: 0.2,
+            theta: -0.14,
+            sigma: 0.2,
+            v0: 0.9,
+            speed: 0.5,
+            eta_v: 0.3,
+            rho: -0.2,
+        };
+        let result = check_vg_parameters(&parameters, &VG_CONSTRAINTS);
+        assert!(result.is_ok());
+    }
+    #[test]
+    fn test_check_vg_parameters_err() {
+        //each bound holds but the exponential moment does not exist
+        let parameters = VGParameters {
+            sigma_vg: 0.12,
+            nu: 2.0,
+            theta: 0.8,
+            sigma: 0.2,
+            v0: 0.9,
+            speed: 0.5,
+            eta_v: 0.3,
+            rho: -0.2,
+        };
+        let result = check_vg_parameters(&parameters, &VG_CONSTRAINTS);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Parameter nu out of bounds."
+        );
+    }
+    #[test]
+    fn test_check_nig_parameters_ok() {
+        let parameters = NIGParameters {
+            alpha: 15.0,
+            beta: -5.0,
+            delta: 0.5,
+            sigma: 0.2,
+            v0: 0.9,
+            speed: 0.5,
+            eta_v: 0.3,
+            rho: -0.2,
+        };
+        let result = check_nig_parameters(&parameters, &NIG_CONSTRAINTS);
+        assert!(result.is_ok());
+    }
+    #[test]
+    fn test_check_nig_parameters_err() {
+        //each bound holds but the exponential moment does not exist
+        let parameters = NIGParameters {
+            alpha: 5.0,
+            beta: 4.5,
+            delta: 0.5,
+            sigma: 0.2,
+            v0: 0.9,
+            speed: 0.5,
+            eta_v: 0.3,
+            rho: -0.2,
+        };
+        let result = check_nig_parameters(&parameters, &NIG_CONSTRAINTS);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Parameter beta out of bounds."
+        );
+    }
+    #[test]
     fn test_check_cgmy_parameters_ok() {
         let parameters = CGMYParameters {
             c: 0.5,
@@ -864,6 +2302,7 @@ mod tests {
             "rate": 0.05,
             "num_u": 8,
             "cf_parameters":{
+                "model":"heston",
                 "sigma":0.5,
                 "speed":0.1,
                 "v0":0.2,
@@ -880,12 +2319,34 @@ mod tests {
         }
     }
     #[test]
+    fn test_serialization_auto_num_u() {
+        let json_str = r#"{
+            "maturity": 0.5,
+            "rate": 0.05,
+            "num_u": "auto",
+            "cf_parameters":{
+                "model":"blackscholes",
+                "sigma":0.3
+            }
+        }"#;
+        let parameters: OptionParameters = serde_json::from_str(json_str).unwrap();
+        assert_eq!(parameters.num_u, NumU::Auto(Precision::Auto));
+        assert!(parameters.num_u.exponent().is_err());
+        assert_eq!(
+            check_parameters(&parameters, &PARAMETER_CONSTRAINTS)
+                .unwrap_err()
+                .to_string(),
+            "Parameter tolerance does not exist."
+        );
+    }
+    #[test]
     fn test_serialization_merton() {
         let json_str = r#"{
             "maturity": 0.5,
             "rate": 0.05,
             "num_u": 8,
             "cf_parameters":{
+                "model":"merton",
                 "sigma":0.5,
                 "speed":0.1,
                 "v0":0.2,
@@ -911,6 +2372,7 @@ mod tests {
             "rate": 0.05,
             "num_u": 8,
             "cf_parameters":{
+                "model":"cgmy",
                 "sigma":0.5,
                 "speed":0.1,
                 "v0":0.2,
@@ -937,6 +2399,7 @@ mod tests {
             "rate": 0.05,
             "num_u": 8,
             "cf_parameters":{
+                "model":"cgmyse",
                 "sigma":0.5,
                 "speed":0.1,
                 "v0":0.2,
@@ -955,4 +2418,174 @@ mod tests {
             _ => assert!(false),
         }
     }
+    #[test]
+    fn test_parse_model_parameters_from_path() {
+        let body = json!({
+            "maturity": 0.5,
+            "rate": 0.05,
+            "num_u": 8,
+            "cf_parameters": {
+                "sigma": 0.5,
+                "speed": 0.1,
+                "v0": 0.2,
+                "eta_v": 0.1,
+                "rho": -0.5
+            }
+        });
+        let parameters: OptionParameters = parse_model_parameters("heston", body).unwrap();
+        assert_eq!(parameters.rates.rate, Some(0.05));
+        match parameters.cf_parameters {
+            CFParameters::Heston(cf_params) => {
+                assert_eq!(cf_params.sigma, 0.5);
+            }
+            _ => panic!("expected heston parameters"),
+        }
+    }
+    #[test]
+    fn test_parse_model_parameters_mismatch() {
+        let body = json!({
+            "maturity": 0.5,
+            "rate": 0.05,
+            "num_u": 8,
+            "cf_parameters": {
+                "model": "merton",
+                "sigma": 0.5,
+                "speed": 0.1,
+                "v0": 0.2,
+                "eta_v": 0.1,
+                "rho": -0.5,
+                "lambda": 0.5,
+                "mu_l": -0.05,
+                "sig_l": 0.3
+            }
+        });
+        let result: Result<OptionParameters, ParameterError> =
+            parse_model_parameters("heston", body);
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Model merton in body does not match model heston in path."
+        );
+    }
+    #[test]
+    fn test_parse_model_parameters_non_string_model() {
+        let body = json!({
+            "maturity": 0.5,
+            "rate": 0.05,
+            "num_u": 8,
+            "cf_parameters": {
+                "model": 1,
+                "sigma": 0.5,
+                "speed": 0.1,
+                "v0": 0.2,
+                "eta_v": 0.1,
+                "rho": -0.5
+            }
+        });
+        let result: Result<OptionParameters, ParameterError> =
+            parse_model_parameters("heston", body);
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Parameter model must be a string."
+        );
+    }
+    #[test]
+    fn test_parse_model_parameters_extra_field() {
+        //heston parameters with a merton field
+        let body = json!({
+            "maturity": 0.5,
+            "rate": 0.05,
+            "num_u": 8,
+            "cf_parameters": {
+                "sigma": 0.5,
+                "speed": 0.1,
+                "v0": 0.2,
+                "eta_v": 0.1,
+                "rho": -0.5,
+                "lambda": 0.5
+            }
+        });
+        let result: Result<OptionParameters, ParameterError> =
+            parse_model_parameters("heston", body);
+        assert!(result.is_err());
+    }
+    #[test]
+    fn test_parse_model_parameters_missing_field() {
+        //valid cgmyse parameters, but cgmy requires rho
+        let body = json!({
+            "maturity": 0.5,
+            "rate": 0.05,
+            "num_u": 8,
+            "cf_parameters": {
+                "sigma": 0.5,
+                "speed": 0.1,
+                "v0": 0.2,
+                "eta_v": 0.1,
+                "c": 0.5,
+                "g": 3.0,
+                "m": 4.0,
+                "y": 0.5
+            }
+        });
+        let result: Result<OptionParameters, ParameterError> = parse_model_parameters("cgmy", body);
+        assert!(result.is_err());
+    }
+    #[test]
+    fn test_cf_parameters_from_model() {
+        for model in [
+            HESTON_NAME,
+            MERTON_NAME,
+            CGMY_NAME,
+            CGMYSE_NAME,
+            KOU_NAME,
+            VG_NAME,
+            NIG_NAME,
+            BATES_NAME,
+            BLACK_SCHOLES_NAME,
+        ] {
+            let cf_parameters = CFParameters::from_model(model).unwrap();
+            assert_eq!(json!(cf_parameters)["model"], json!(model));
+            let values: Vec<f64> = cf_parameters
+                .to_vector()
+                .into_iter()
+                .map(|(value, _)| value)
+                .collect();
+            let lower: Vec<f64> = cf_parameters
+                .bounds()
+                .into_iter()
+                .map(|(lower, _)| lower)
+                .collect();
+            assert_eq!(values, lower);
+        }
+        assert_eq!(
+            CFParameters::from_model("hello").err().unwrap().to_string(),
+            "Parameter hello does not exist."
+        );
+    }
+    #[test]
+    fn test_serialization_calibration() {
+        let json_str = r#"{
+            "asset": 178.46,
+            "num_u": 8,
+            "rate": 0.0,
+            "objective": "implied_volatility",
+            "option_data": [
+                {
+                    "maturity": 1.0,
+                    "option_data": [
+                        {"price": 83.64, "strike": 95},
+                        {"implied_volatility": 0.3, "strike": 100}
+                    ]
+                }
+            ]
+        }"#;
+        let parameters: CalibrationParameters = serde_json::from_str(json_str).unwrap();
+        assert_eq!(
+            parameters.objective,
+            Some(CalibrationObjective::ImpliedVolatility)
+        );
+        let quotes = &parameters.option_data[0].option_data;
+        assert_eq!(quotes[0].price, Some(83.64));
+        assert_eq!(quotes[1].implied_volatility, Some(0.3));
+        assert!(check_calibration_parameters(&parameters, &PARAMETER_CONSTRAINTS).is_ok());
+    }
 }
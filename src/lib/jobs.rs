@@ -0,0 +1,260 @@
+use crate::calibration::OptimizationProgress;
+use crate::constraints::{throw_no_exist_error, ErrorType, ParameterError};
+use rocket::serde::json::Value;
+use serde_derive::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Serialize)]
+pub struct JobProgress {
+    pub iterations: usize,
+    pub max_iterations: usize,
+    pub best_cost: f64, //null until the first iteration completes
+}
+
+#[derive(Serialize)]
+pub struct JobResponse {
+    pub id: String,
+    pub model: String,
+    pub status: JobStatus,
+    pub error: Option<String>,
+}
+
+struct Job {
+    model: String,
+    max_iterations: usize,
+    progress: Arc<OptimizationProgress>,
+    outcome: Option<Result<Value, String>>,
+    finished: Option<Instant>,
+}
+
+impl Job {
+    fn status(&self) -> JobStatus {
+        match (&self.outcome, self.progress.is_cancelled()) {
+            (_, true) => JobStatus::Cancelled,
+            (None, false) => JobStatus::Running,
+            (Some(Ok(_)), false) => JobStatus::Completed,
+            (Some(Err(_)), false) => JobStatus::Failed,
+        }
+    }
+    fn to_response(&self, id: &str) -> JobResponse {
+        JobResponse {
+            id: id.to_string(),
+            model: self.model.clone(),
+            status: self.status(),
+            error: match &self.outcome {
+                Some(Err(message)) => Some(message.clone()),
+                _ => None,
+            },
+        }
+    }
+    fn to_progress(&self) -> JobProgress {
+        JobProgress {
+            iterations: self.progress.iterations(),
+            max_iterations: self.max_iterations,
+            best_cost: self.progress.best_cost(),
+        }
+    }
+}
+
+/// In-memory store of long running calibrations.  Jobs
+/// are evicted `ttl` after they finish; running jobs are
+/// never evicted.
+#[derive(Clone)]
+pub struct JobStore {
+    jobs: Arc<Mutex<HashMap<String, Job>>>,
+    ttl: Duration,
+}
+
+fn throw_job_error(message: &str) -> ParameterError {
+    ParameterError::new(&ErrorType::JobError(message.to_string()))
+}
+
+/// Looks up a job submitted for `model`; a job submitted
+/// for another model does not exist under this one
+fn get_job<'a>(
+    jobs: &'a HashMap<String, Job>,
+    model: &str,
+    id: &str,
+) -> Result<&'a Job, ParameterError> {
+    jobs.get(id)
+        .filter(|job| job.model == model)
+        .ok_or_else(|| throw_no_exist_error("job"))
+}
+
+impl JobStore {
+    pub fn new(ttl: Duration) -> Self {
+        JobStore {
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            ttl,
+        }
+    }
+    /// Locks the store, evicting expired jobs first
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Job>> {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|err| err.into_inner());
+        let ttl = self.ttl;
+        jobs.retain(|_, job| {
+            job.finished
+                .map_or(true, |finished| finished.elapsed() < ttl)
+        });
+        jobs
+    }
+    /// Registers a new running job, returning its id and
+    /// the progress handle to pass to the calibration
+    pub fn submit(
+        &self,
+        model: &str,
+        max_iterations: usize,
+    ) -> (String, Arc<OptimizationProgress>) {
+        let id = format!("{:032x}", rand::random::<u128>());
+        let progress = Arc::new(OptimizationProgress::default());
+        self.lock().insert(
+            id.clone(),
+            Job {
+                model: model.to_string(),
+                max_iterations,
+                progress: progress.clone(),
+                outcome: None,
+                finished: None,
+            },
+        );
+        (id, progress)
+    }
+    /// Stores the outcome of a job; does nothing if the
+    /// job has already been evicted
+    pub fn finish(&self, id: &str, outcome: Result<Value, ParameterError>) {
+        if let Some(job) = self.lock().get_mut(id) {
+            job.outcome = Some(outcome.map_err(|err| err.to_string()));
+            job.finished = Some(Instant::now());
+        }
+    }
+    pub fn get_status(&self, model: &str, id: &str) -> Result<JobResponse, ParameterError> {
+        get_job(&self.lock(), model, id).map(|job| job.to_response(id))
+    }
+    pub fn get_progress(&self, model: &str, id: &str) -> Result<JobProgress, ParameterError> {
+        get_job(&self.lock(), model, id).map(|job| job.to_progress())
+    }
+    pub fn get_result(&self, model: &str, id: &str) -> Result<Value, ParameterError> {
+        let jobs = self.lock();
+        let job = get_job(&jobs, model, id)?;
+        match (job.status(), &job.outcome) {
+            (JobStatus::Completed, Some(Ok(result))) => Ok(result.clone()),
+            (JobStatus::Failed, Some(Err(message))) => Err(throw_job_error(message)),
+            (JobStatus::Cancelled, _) => Err(throw_job_error("Job was cancelled")),
+            _ => Err(throw_job_error("Job is still running")),
+        }
+    }
+    /// Asks a running job to stop.  The calibration checks
+    /// the flag between evaluations, so the job finishes
+    /// shortly after rather than immediately.
+    pub fn cancel(&self, model: &str, id: &str) -> Result<JobResponse, ParameterError> {
+        let jobs = self.lock();
+        let job = get_job(&jobs, model, id)?;
+        if job.outcome.is_none() {
+            job.progress.cancel();
+        }
+        Ok(job.to_response(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jobs::*;
+    use rocket::serde::json::json;
+    #[test]
+    fn test_job_lifecycle() {
+        let store = JobStore::new(Duration::from_secs(60));
+        let (id, _) = store.submit("heston", 10);
+        assert_eq!(
+            store.get_status("heston", &id).unwrap().status,
+            JobStatus::Running
+        );
+        assert!(store.get_result("heston", &id).is_err());
+        store.finish(&id, Ok(json!({"final_cost_value": 0.1})));
+        assert_eq!(
+            store.get_status("heston", &id).unwrap().status,
+            JobStatus::Completed
+        );
+        assert_eq!(
+            store.get_result("heston", &id).unwrap(),
+            json!({"final_cost_value": 0.1})
+        );
+    }
+    #[test]
+    fn test_job_does_not_exist() {
+        let store = JobStore::new(Duration::from_secs(60));
+        assert_eq!(
+            store.get_status("heston", "abc").err().unwrap(),
+            throw_no_exist_error("job")
+        );
+    }
+    #[test]
+    fn test_job_cancel() {
+        let store = JobStore::new(Duration::from_secs(60));
+        let (id, progress) = store.submit("heston", 10);
+        let response = store.cancel("heston", &id).unwrap();
+        assert_eq!(response.status, JobStatus::Cancelled);
+        assert!(progress.is_cancelled());
+        //finishing a cancelled job keeps it cancelled
+        store.finish(&id, Ok(json!({})));
+        assert_eq!(
+            store.get_status("heston", &id).unwrap().status,
+            JobStatus::Cancelled
+        );
+        assert!(store.get_result("heston", &id).is_err());
+    }
+    #[test]
+    fn test_job_cancel_after_completion() {
+        let store = JobStore::new(Duration::from_secs(60));
+        let (id, progress) = store.submit("heston", 10);
+        store.finish(&id, Ok(json!({})));
+        assert_eq!(
+            store.cancel("heston", &id).unwrap().status,
+            JobStatus::Completed
+        );
+        assert!(!progress.is_cancelled());
+    }
+    #[test]
+    fn test_job_eviction() {
+        let store = JobStore::new(Duration::from_millis(0));
+        let (running_id, _) = store.submit("heston", 10);
+        let (finished_id, _) = store.submit("heston", 10);
+        store.finish(&finished_id, Ok(json!({})));
+        assert!(store.get_status("heston", &finished_id).is_err());
+        assert!(store.get_status("heston", &running_id).is_ok());
+    }
+    #[test]
+    fn test_job_progress() {
+        let store = JobStore::new(Duration::from_secs(60));
+        let (id, _) = store.submit("heston", 10);
+        let progress = store.get_progress("heston", &id).unwrap();
+        assert_eq!(progress.iterations, 0);
+        assert_eq!(progress.max_iterations, 10);
+    }
+    #[test]
+    fn test_job_requires_its_model() {
+        let store = JobStore::new(Duration::from_secs(60));
+        let (id, _) = store.submit("heston", 10);
+        assert_eq!(
+            store.get_status("merton", &id).err().unwrap(),
+            throw_no_exist_error("job")
+        );
+        assert!(store.get_progress("merton", &id).is_err());
+        assert!(store.get_result("merton", &id).is_err());
+        assert!(store.cancel("merton", &id).is_err());
+        assert_eq!(
+            store.get_status("heston", &id).unwrap().status,
+            JobStatus::Running
+        );
+    }
+}
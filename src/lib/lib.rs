@@ -1,4 +1,8 @@
+pub mod arbitrage;
 pub mod auth;
+pub mod calibration;
+pub mod cf_models;
 pub mod constants;
 pub mod constraints;
+pub mod jobs;
 pub mod pricing_maps;
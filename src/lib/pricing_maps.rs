@@ -1,57 +1,150 @@
-use crate::constants::{
-    CALL_DELTA, CALL_GAMMA, CALL_PRICE, CALL_THETA, DENSITY, PUT_DELTA, PUT_GAMMA, PUT_PRICE,
-    PUT_THETA, RISK_MEASURES,
-};
+use crate::arbitrage::{self, ArbitrageViolation};
+use crate::cf_models::{self, Cumulants, LevyCumulants, TimeChange};
 use crate::constraints::{
-    check_cgmy_parameters, check_cgmyse_parameters, check_heston_parameters,
-    check_merton_parameters, throw_no_convergence_error, CFParameters, CGMYParameters,
-    CGMYSEParameters, ErrorType, HestonParameters, MertonParameters, ParameterError,
-    CGMYSE_CONSTRAINTS, CGMY_CONSTRAINTS, HESTON_CONSTRAINTS, MERTON_CONSTRAINTS,
+    check_bates_parameters, check_black_scholes_parameters, check_cgmy_parameters,
+    check_cgmyse_parameters, check_heston_parameters, check_kou_parameters,
+    check_merton_parameters, check_nig_parameters, check_vg_parameters, throw_no_convergence_error,
+    throw_no_exist_error, BatesParameters, BlackScholesParameters, CFParameters, CGMYParameters,
+    CGMYSEParameters, CashDividend, ErrorType, HestonParameters, KouParameters, MertonParameters,
+    NIGParameters, ParameterError, RateParameters, SurfaceParameters, VGParameters, ZeroRate,
+    BATES_CONSTRAINTS, BLACK_SCHOLES_CONSTRAINTS, CGMYSE_CONSTRAINTS, CGMY_CONSTRAINTS,
+    HESTON_CONSTRAINTS, KOU_CONSTRAINTS, MERTON_CONSTRAINTS, NIG_CONSTRAINTS,
+    PARAMETER_CONSTRAINTS, VG_CONSTRAINTS,
 };
 
 use fang_oost_option::option_pricing;
 use num_complex::Complex;
 use rayon::prelude::*;
+use rocket::request::FromParam;
 use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OptionKind {
+    Call,
+    Put,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sensitivity {
+    Price,
+    Delta,
+    Gamma,
+    Theta,
+    Vega,
+    Rho,
+    Vanna,
+    Volga,
+    Charm,
+    Speed,
+    ParameterSensitivities,
+}
 
-/// Gets indicators for which sensitivity
-/// to retrieve
+/// Parses the option type from a route segment
 /// # Examples
 ///
 /// ```
+/// extern crate rocket;
 /// extern crate utils;
-/// use utils::pricing_maps;
+/// use rocket::request::FromParam;
+/// use utils::pricing_maps::OptionKind;
 /// # fn main() {
-/// let sensitivity = pricing_maps::get_fn_indicators(
-///     "put",
-///     "price"
-/// ).unwrap();
+/// let option_kind = OptionKind::from_param("put").unwrap();
+/// assert_eq!(option_kind, OptionKind::Put);
 /// # }
 /// ```
-pub fn get_fn_indicators(option_type: &str, sensitivity: &str) -> Result<i32, ParameterError> {
-    let combine_types = format!("{}_{}", option_type, sensitivity);
-    match combine_types.as_str() {
-        "put_price" => Ok(PUT_PRICE),
-        "call_price" => Ok(CALL_PRICE),
-        "put_delta" => Ok(PUT_DELTA),
-        "call_delta" => Ok(CALL_DELTA),
-        "put_gamma" => Ok(PUT_GAMMA),
-        "call_gamma" => Ok(CALL_GAMMA),
-        "put_theta" => Ok(PUT_THETA),
-        "call_theta" => Ok(CALL_THETA),
-        "density_" => Ok(DENSITY),
-        "riskmetric_" => Ok(RISK_MEASURES),
-        _ => Err(ParameterError::new(&ErrorType::FunctionError(
-            combine_types,
-        ))),
+impl<'a> FromParam<'a> for OptionKind {
+    type Error = ParameterError;
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        match param {
+            "call" => Ok(OptionKind::Call),
+            "put" => Ok(OptionKind::Put),
+            _ => Err(ParameterError::new(&ErrorType::FunctionError(
+                param.to_string(),
+            ))),
+        }
+    }
+}
+
+/// Parses the sensitivity from a route segment
+impl<'a> FromParam<'a> for Sensitivity {
+    type Error = ParameterError;
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        match param {
+            "price" => Ok(Sensitivity::Price),
+            "delta" => Ok(Sensitivity::Delta),
+            "gamma" => Ok(Sensitivity::Gamma),
+            "theta" => Ok(Sensitivity::Theta),
+            "vega" => Ok(Sensitivity::Vega),
+            "rho" => Ok(Sensitivity::Rho),
+            "vanna" => Ok(Sensitivity::Vanna),
+            "volga" => Ok(Sensitivity::Volga),
+            "charm" => Ok(Sensitivity::Charm),
+            "speed" => Ok(Sensitivity::Speed),
+            "parameter_sensitivities" => Ok(Sensitivity::ParameterSensitivities),
+            _ => Err(ParameterError::new(&ErrorType::FunctionError(
+                param.to_string(),
+            ))),
+        }
+    }
+}
+
+/// Whether implied volatilities are returned, and whether
+/// a strike without one fails the request
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImpliedVolatilityMode {
+    Skip,
+    Lenient,
+    Strict,
+}
+
+impl ImpliedVolatilityMode {
+    pub fn new(include_iv: bool, strict_iv: bool) -> Self {
+        match (include_iv, strict_iv) {
+            (false, _) => ImpliedVolatilityMode::Skip,
+            (true, false) => ImpliedVolatilityMode::Lenient,
+            (true, true) => ImpliedVolatilityMode::Strict,
+        }
     }
 }
 
+/// Discretization, maturity and the rates resolved at that
+/// maturity, shared by the pricing and distribution routes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PricingParameters {
+    pub num_u: usize, //number of discrete "u", not its exponent
+    pub scale: f64,   //truncation scale
+    pub maturity: f64,
+    pub rate: f64,
+    pub dividend_yield: f64, //includes the borrow rate
+    pub cash_dividend_shift: f64,
+}
+
+impl PricingParameters {
+    pub fn discount(&self) -> f64 {
+        (-self.rate * self.maturity).exp()
+    }
+    /// Forward of the asset, net of cash dividends
+    pub fn forward(&self, asset: f64) -> f64 {
+        asset * (self.cash_dividend_shift + (self.rate - self.dividend_yield) * self.maturity).exp()
+    }
+}
+
+/// Characteristic function of the log asset return, boxed
+/// so that every model has the same type
+type ModelCf = Box<dyn Fn(&Complex<f64>) -> Complex<f64> + Sync + Send>;
+
+/// Lowest and highest log return of the truncation range
+type TruncationRange = (f64, f64);
+
 fn get_cgmy_cf(
     cf_parameters: &CGMYParameters,
     maturity: f64,
     rate: f64,
-) -> Result<(impl Fn(&Complex<f64>) -> Complex<f64>, f64), ParameterError> {
+    dividend_yield: f64,
+) -> Result<(ModelCf, Cumulants), ParameterError> {
     check_cgmy_parameters(&cf_parameters, &CGMY_CONSTRAINTS)?;
     let CGMYParameters {
         c,
@@ -65,16 +158,39 @@ fn get_cgmy_cf(
         rho,
     } = cf_parameters;
     let cf_inst = cf_functions::cgmy::cgmy_time_change_cf(
-        maturity, rate, *c, *g, *m, *y, *sigma, *v0, *speed, *eta_v, *rho,
+        maturity,
+        rate - dividend_yield,
+        *c,
+        *g,
+        *m,
+        *y,
+        *sigma,
+        *v0,
+        *speed,
+        *eta_v,
+        *rho,
     );
-    let vol = cf_functions::cgmy::cgmy_diffusion_vol(*sigma, *c, *g, *m, *y, maturity);
-    Ok((cf_inst, vol))
+    let jump_log_mgf = cf_functions::cgmy::cgmy_log_cf(&Complex::new(1.0, 0.0), *c, *g, *m, *y).re;
+    let cumulants = cf_models::time_change_cumulants(
+        maturity,
+        rate - dividend_yield,
+        &cf_models::cgmy_cumulants(*c, *g, *m, *y).risk_neutral(*sigma, jump_log_mgf),
+        &TimeChange {
+            sigma: *sigma,
+            v0: *v0,
+            speed: *speed,
+            eta_v: *eta_v,
+            rho: *rho,
+        },
+    );
+    Ok((Box::new(cf_inst), cumulants))
 }
 fn get_cgmyse_cf(
     cf_parameters: &CGMYSEParameters,
     maturity: f64,
     rate: f64,
-) -> Result<(impl Fn(&Complex<f64>) -> Complex<f64>, f64), ParameterError> {
+    dividend_yield: f64,
+) -> Result<(ModelCf, Cumulants), ParameterError> {
     check_cgmyse_parameters(&cf_parameters, &CGMYSE_CONSTRAINTS)?;
     let num_steps = 256; //
     let CGMYSEParameters {
@@ -89,16 +205,42 @@ fn get_cgmyse_cf(
         //rho,
     } = cf_parameters;
     let cf_inst = cf_functions::cgmy::cgmyse_time_change_cf(
-        maturity, rate, *c, *g, *m, *y, *sigma, *v0, *speed, *eta_v, num_steps,
+        maturity,
+        rate - dividend_yield,
+        *c,
+        *g,
+        *m,
+        *y,
+        *sigma,
+        *v0,
+        *speed,
+        *eta_v,
+        num_steps,
+    );
+    //the jumps are scaled by sigma, and the self exciting
+    //time change only enters through its mean
+    let jump_log_mgf =
+        cf_functions::cgmy::cgmy_log_cf(&Complex::new(*sigma, 0.0), *c, *g, *m, *y).re;
+    let cumulants = cf_models::time_change_cumulants(
+        maturity,
+        rate - dividend_yield,
+        &cf_models::cgmy_cumulants(*c, *g, *m, *y)
+            .scale(*sigma)
+            .risk_neutral(0.0, jump_log_mgf),
+        &TimeChange {
+            v0: *v0,
+            speed: *speed,
+            ..TimeChange::none()
+        },
     );
-    let vol = cf_functions::cgmy::cgmy_diffusion_vol(*sigma, *c, *g, *m, *y, maturity);
-    Ok((cf_inst, vol))
+    Ok((Box::new(cf_inst), cumulants))
 }
 fn get_merton_cf(
     cf_parameters: &MertonParameters,
     maturity: f64,
     rate: f64,
-) -> Result<(impl Fn(&Complex<f64>) -> Complex<f64>, f64), ParameterError> {
+    dividend_yield: f64,
+) -> Result<(ModelCf, Cumulants), ParameterError> {
     check_merton_parameters(&cf_parameters, &MERTON_CONSTRAINTS)?;
     let MertonParameters {
         lambda,
@@ -111,17 +253,40 @@ fn get_merton_cf(
         rho,
     } = cf_parameters;
     let cf_inst = cf_functions::merton::merton_time_change_cf(
-        maturity, rate, *lambda, *mu_l, *sig_l, *sigma, *v0, *speed, *eta_v, *rho,
+        maturity,
+        rate - dividend_yield,
+        *lambda,
+        *mu_l,
+        *sig_l,
+        *sigma,
+        *v0,
+        *speed,
+        *eta_v,
+        *rho,
     );
-    let vol = cf_functions::merton::jump_diffusion_vol(*sigma, *lambda, *mu_l, *sig_l, maturity);
-    Ok((cf_inst, vol))
+    let jump_log_mgf =
+        cf_functions::merton::merton_log_cf(&Complex::new(1.0, 0.0), *lambda, *mu_l, *sig_l).re;
+    let cumulants = cf_models::time_change_cumulants(
+        maturity,
+        rate - dividend_yield,
+        &cf_models::merton_cumulants(*lambda, *mu_l, *sig_l).risk_neutral(*sigma, jump_log_mgf),
+        &TimeChange {
+            sigma: *sigma,
+            v0: *v0,
+            speed: *speed,
+            eta_v: *eta_v,
+            rho: *rho,
+        },
+    );
+    Ok((Box::new(cf_inst), cumulants))
 }
 
 fn get_heston_cf(
     cf_parameters: &HestonParameters,
     maturity: f64,
     rate: f64,
-) -> Result<(impl Fn(&Complex<f64>) -> Complex<f64>, f64), ParameterError> {
+    dividend_yield: f64,
+) -> Result<(ModelCf, Cumulants), ParameterError> {
     check_heston_parameters(&cf_parameters, &HESTON_CONSTRAINTS)?;
     let HestonParameters {
         sigma,
@@ -130,129 +295,918 @@ fn get_heston_cf(
         eta_v,
         rho,
     } = cf_parameters;
-    let cf_inst = cf_functions::gauss::heston_cf(maturity, rate, *sigma, *v0, *speed, *eta_v, *rho);
-    Ok((cf_inst, sigma * maturity.sqrt()))
+    let cf_inst = cf_functions::gauss::heston_cf(
+        maturity,
+        rate - dividend_yield,
+        *sigma,
+        *v0,
+        *speed,
+        *eta_v,
+        *rho,
+    );
+    let cumulants = cf_models::time_change_cumulants(
+        maturity,
+        rate - dividend_yield,
+        &LevyCumulants::default().risk_neutral(*sigma, 0.0),
+        &TimeChange::heston(*sigma, *v0, *speed, *eta_v, *rho),
+    );
+    Ok((Box::new(cf_inst), cumulants))
+}
+
+fn get_kou_cf(
+    cf_parameters: &KouParameters,
+    maturity: f64,
+    rate: f64,
+    dividend_yield: f64,
+) -> Result<(ModelCf, Cumulants), ParameterError> {
+    check_kou_parameters(cf_parameters, &KOU_CONSTRAINTS)?;
+    let KouParameters {
+        lambda,
+        p,
+        eta_up,
+        eta_down,
+        sigma,
+        v0,
+        speed,
+        eta_v,
+        rho,
+    } = cf_parameters;
+    let time_change = TimeChange {
+        sigma: *sigma,
+        v0: *v0,
+        speed: *speed,
+        eta_v: *eta_v,
+        rho: *rho,
+    };
+    let cf_inst = cf_models::kou_time_change_cf(
+        maturity,
+        rate - dividend_yield,
+        *lambda,
+        *p,
+        *eta_up,
+        *eta_down,
+        &time_change,
+    );
+    let jump_log_mgf =
+        cf_models::kou_log_cf(&Complex::new(1.0, 0.0), *lambda, *p, *eta_up, *eta_down).re;
+    let cumulants = cf_models::time_change_cumulants(
+        maturity,
+        rate - dividend_yield,
+        &cf_models::kou_cumulants(*lambda, *p, *eta_up, *eta_down)
+            .risk_neutral(*sigma, jump_log_mgf),
+        &time_change,
+    );
+    Ok((Box::new(cf_inst), cumulants))
+}
+
+fn get_vg_cf(
+    cf_parameters: &VGParameters,
+    maturity: f64,
+    rate: f64,
+    dividend_yield: f64,
+) -> Result<(ModelCf, Cumulants), ParameterError> {
+    check_vg_parameters(cf_parameters, &VG_CONSTRAINTS)?;
+    let VGParameters {
+        sigma_vg,
+        nu,
+        theta,
+        sigma,
+        v0,
+        speed,
+        eta_v,
+        rho,
+    } = cf_parameters;
+    let time_change = TimeChange {
+        sigma: *sigma,
+        v0: *v0,
+        speed: *speed,
+        eta_v: *eta_v,
+        rho: *rho,
+    };
+    let cf_inst = cf_models::vg_time_change_cf(
+        maturity,
+        rate - dividend_yield,
+        *sigma_vg,
+        *nu,
+        *theta,
+        &time_change,
+    );
+    let jump_log_mgf = cf_models::vg_log_cf(&Complex::new(1.0, 0.0), *sigma_vg, *nu, *theta).re;
+    let cumulants = cf_models::time_change_cumulants(
+        maturity,
+        rate - dividend_yield,
+        &cf_models::vg_cumulants(*sigma_vg, *nu, *theta).risk_neutral(*sigma, jump_log_mgf),
+        &time_change,
+    );
+    Ok((Box::new(cf_inst), cumulants))
+}
+
+fn get_nig_cf(
+    cf_parameters: &NIGParameters,
+    maturity: f64,
+    rate: f64,
+    dividend_yield: f64,
+) -> Result<(ModelCf, Cumulants), ParameterError> {
+    check_nig_parameters(cf_parameters, &NIG_CONSTRAINTS)?;
+    let NIGParameters {
+        alpha,
+        beta,
+        delta,
+        sigma,
+        v0,
+        speed,
+        eta_v,
+        rho,
+    } = cf_parameters;
+    let time_change = TimeChange {
+        sigma: *sigma,
+        v0: *v0,
+        speed: *speed,
+        eta_v: *eta_v,
+        rho: *rho,
+    };
+    let cf_inst = cf_models::nig_time_change_cf(
+        maturity,
+        rate - dividend_yield,
+        *alpha,
+        *beta,
+        *delta,
+        &time_change,
+    );
+    let jump_log_mgf = cf_models::nig_log_cf(&Complex::new(1.0, 0.0), *alpha, *beta, *delta).re;
+    let cumulants = cf_models::time_change_cumulants(
+        maturity,
+        rate - dividend_yield,
+        &cf_models::nig_cumulants(*alpha, *beta, *delta).risk_neutral(*sigma, jump_log_mgf),
+        &time_change,
+    );
+    Ok((Box::new(cf_inst), cumulants))
+}
+
+fn get_bates_cf(
+    cf_parameters: &BatesParameters,
+    maturity: f64,
+    rate: f64,
+    dividend_yield: f64,
+) -> Result<(ModelCf, Cumulants), ParameterError> {
+    check_bates_parameters(cf_parameters, &BATES_CONSTRAINTS)?;
+    let BatesParameters {
+        lambda,
+        mu_l,
+        sig_l,
+        sigma,
+        v0,
+        speed,
+        eta_v,
+        rho,
+    } = cf_parameters;
+    let cf_inst = cf_models::bates_cf(
+        maturity,
+        rate - dividend_yield,
+        *lambda,
+        *mu_l,
+        *sig_l,
+        &HestonParameters {
+            sigma: *sigma,
+            v0: *v0,
+            speed: *speed,
+            eta_v: *eta_v,
+            rho: *rho,
+        },
+    );
+    //the jumps are not time changed
+    let jump_log_mgf =
+        cf_functions::merton::merton_log_cf(&Complex::new(1.0, 0.0), *lambda, *mu_l, *sig_l).re;
+    let cumulants = cf_models::time_change_cumulants(
+        maturity,
+        rate - dividend_yield,
+        &LevyCumulants::default().risk_neutral(*sigma, 0.0),
+        &TimeChange::heston(*sigma, *v0, *speed, *eta_v, *rho),
+    ) + cf_models::time_change_cumulants(
+        maturity,
+        0.0,
+        &cf_models::merton_cumulants(*lambda, *mu_l, *sig_l).risk_neutral(0.0, jump_log_mgf),
+        &TimeChange::none(),
+    );
+    Ok((Box::new(cf_inst), cumulants))
+}
+
+fn get_black_scholes_cf(
+    cf_parameters: &BlackScholesParameters,
+    maturity: f64,
+    rate: f64,
+    dividend_yield: f64,
+) -> Result<(ModelCf, Cumulants), ParameterError> {
+    check_black_scholes_parameters(cf_parameters, &BLACK_SCHOLES_CONSTRAINTS)?;
+    let vol = cf_parameters.sigma * maturity.sqrt();
+    let mu = (rate - dividend_yield - 0.5 * cf_parameters.sigma.powi(2)) * maturity;
+    let cf_inst = move |u: &Complex<f64>| cf_functions::gauss::gauss_cf(u, mu, vol);
+    let cumulants = cf_models::time_change_cumulants(
+        maturity,
+        rate - dividend_yield,
+        &LevyCumulants::default().risk_neutral(cf_parameters.sigma, 0.0),
+        &TimeChange::none(),
+    );
+    Ok((Box::new(cf_inst), cumulants))
+}
+
+/// Returns the model's characteristic function along with the
+/// truncation range of the log return, which is `scale`
+/// cumulant widths around its mean.  Both are moved by the
+/// cash dividend shift, so that they describe the escrowed
+/// asset relative to the asset.
+fn get_model_cf(
+    cf_parameters: &CFParameters,
+    pricing: &PricingParameters,
+) -> Result<(ModelCf, TruncationRange), ParameterError> {
+    let PricingParameters {
+        scale,
+        maturity,
+        rate,
+        dividend_yield,
+        cash_dividend_shift,
+        ..
+    } = *pricing;
+    let (cf_inst, cumulants) = match cf_parameters {
+        CFParameters::CGMY(cf_params) => get_cgmy_cf(cf_params, maturity, rate, dividend_yield),
+        CFParameters::CGMYSE(cf_params) => get_cgmyse_cf(cf_params, maturity, rate, dividend_yield),
+        CFParameters::Merton(cf_params) => get_merton_cf(cf_params, maturity, rate, dividend_yield),
+        CFParameters::Heston(cf_params) => get_heston_cf(cf_params, maturity, rate, dividend_yield),
+        CFParameters::Kou(cf_params) => get_kou_cf(cf_params, maturity, rate, dividend_yield),
+        CFParameters::VG(cf_params) => get_vg_cf(cf_params, maturity, rate, dividend_yield),
+        CFParameters::NIG(cf_params) => get_nig_cf(cf_params, maturity, rate, dividend_yield),
+        CFParameters::Bates(cf_params) => get_bates_cf(cf_params, maturity, rate, dividend_yield),
+        CFParameters::BlackScholes(cf_params) => {
+            get_black_scholes_cf(cf_params, maturity, rate, dividend_yield)
+        }
+    }?;
+    let (x_min, x_max) = cumulants.range(scale);
+    let range = (x_min + cash_dividend_shift, x_max + cash_dividend_shift);
+    if cash_dividend_shift == 0.0 {
+        return Ok((cf_inst, range));
+    }
+    Ok((
+        Box::new(move |u| cf_inst(u) * (u * cash_dividend_shift).exp()),
+        range,
+    ))
+}
+
+/// Returns the closed form Black-Scholes value, so that
+/// the error of the numerical inversion can be seen.  None
+/// for sensitivities without a closed form.
+fn get_black_scholes_closed_form(
+    option_kind: OptionKind,
+    sensitivity: Sensitivity,
+    asset: f64,
+    strike: f64,
+    pricing: &PricingParameters,
+    sigma: f64,
+) -> Option<f64> {
+    let PricingParameters {
+        maturity,
+        rate,
+        dividend_yield,
+        ..
+    } = *pricing;
+    //black scholes with a dividend yield is black scholes
+    //without one on the dividend adjusted asset
+    let dividend_discount = (-dividend_yield * maturity).exp();
+    let adjusted_asset = asset * dividend_discount;
+    let closed_form_fn = |closed_form_fn: fn(f64, f64, f64, f64, f64) -> f64| {
+        closed_form_fn(adjusted_asset, strike, rate, sigma, maturity)
+    };
+    let sqrt_maturity_sigma = sigma * maturity.sqrt();
+    let d1 = ((adjusted_asset / strike).ln() + (rate + 0.5 * sigma.powi(2)) * maturity)
+        / sqrt_maturity_sigma;
+    let d2 = d1 - sqrt_maturity_sigma;
+    let density = (-0.5 * d1.powi(2)).exp() / (2.0 * std::f64::consts::PI).sqrt();
+    let charm_drift = dividend_discount
+        * density
+        * (2.0 * (rate - dividend_yield) * maturity - d2 * sqrt_maturity_sigma)
+        / (2.0 * maturity * sqrt_maturity_sigma);
+    let closed_form = match (option_kind, sensitivity) {
+        (OptionKind::Call, Sensitivity::Price) => closed_form_fn(black_scholes::call),
+        (OptionKind::Put, Sensitivity::Price) => closed_form_fn(black_scholes::put),
+        (OptionKind::Call, Sensitivity::Delta) => {
+            closed_form_fn(black_scholes::call_delta) * dividend_discount
+        }
+        (OptionKind::Put, Sensitivity::Delta) => {
+            closed_form_fn(black_scholes::put_delta) * dividend_discount
+        }
+        (_, Sensitivity::Gamma) => {
+            closed_form_fn(black_scholes::call_gamma) * dividend_discount.powi(2)
+        }
+        (OptionKind::Call, Sensitivity::Theta) => {
+            closed_form_fn(black_scholes::call_theta)
+                + dividend_yield * adjusted_asset * closed_form_fn(black_scholes::call_delta)
+        }
+        (OptionKind::Put, Sensitivity::Theta) => {
+            closed_form_fn(black_scholes::put_theta)
+                + dividend_yield * adjusted_asset * closed_form_fn(black_scholes::put_delta)
+        }
+        (_, Sensitivity::Vega) => closed_form_fn(black_scholes::call_vega),
+        (OptionKind::Call, Sensitivity::Rho) => closed_form_fn(black_scholes::call_rho),
+        (OptionKind::Put, Sensitivity::Rho) => closed_form_fn(black_scholes::put_rho),
+        (_, Sensitivity::Vanna) => -dividend_discount * density * d2 / sigma,
+        (_, Sensitivity::Volga) => closed_form_fn(black_scholes::call_vega) * d1 * d2 / sigma,
+        (OptionKind::Call, Sensitivity::Charm) => {
+            dividend_yield * dividend_discount * closed_form_fn(black_scholes::call_delta)
+                - charm_drift
+        }
+        (OptionKind::Put, Sensitivity::Charm) => {
+            dividend_yield * dividend_discount * closed_form_fn(black_scholes::put_delta)
+                - charm_drift
+        }
+        (_, Sensitivity::Speed) => {
+            -closed_form_fn(black_scholes::call_gamma) * dividend_discount.powi(2) / asset
+                * (d1 / sqrt_maturity_sigma + 1.0)
+        }
+        //the sensitivities to sigma are vega
+        (_, Sensitivity::ParameterSensitivities) => return None,
+    };
+    Some(closed_form)
 }
 
-pub(crate) fn get_max_strike(asset: f64, option_scale: f64, vol: f64) -> f64 {
-    (option_scale * vol).exp() * asset
+/// `fang_oost_option` integrates over a range that is
+/// symmetric in the log of the asset over the strike, so
+/// the largest strike covers the wider side of the range
+fn get_max_strike(asset: f64, (x_min, x_max): TruncationRange) -> f64 {
+    x_min.abs().max(x_max.abs()).exp() * asset
+}
+/// These are not prices, so have no implied volatility
+fn check_implied_volatility_mode(
+    sensitivity: Sensitivity,
+    iv_mode: ImpliedVolatilityMode,
+) -> Result<(), ParameterError> {
+    if iv_mode != ImpliedVolatilityMode::Skip
+        && matches!(
+            sensitivity,
+            Sensitivity::Vega
+                | Sensitivity::Vanna
+                | Sensitivity::Volga
+                | Sensitivity::Charm
+                | Sensitivity::ParameterSensitivities
+        )
+    {
+        return Err(ParameterError::new(&ErrorType::UnsupportedOption(
+            "include_implied_volatility".to_string(),
+            format!("{:?}", sensitivity),
+        )));
+    }
+    Ok(())
 }
 pub fn get_option_results_as_json(
-    fn_choice: i32,
-    include_iv: bool,
+    option_kind: OptionKind,
+    sensitivity: Sensitivity,
+    iv_mode: ImpliedVolatilityMode,
     cf_parameters: &CFParameters,
-    option_scale: f64,
-    num_u: usize,
+    pricing: &PricingParameters,
     asset: f64,
-    maturity: f64,
-    rate: f64,
     strikes: &[f64],
 ) -> Result<Vec<GraphElement>, ParameterError> {
-    match cf_parameters {
-        CFParameters::CGMY(cf_params) => {
-            let (cf_inst, vol) = get_cgmy_cf(cf_params, maturity, rate)?;
-            let max_strike = get_max_strike(asset, option_scale, vol);
-            get_option_results(
-                fn_choice, include_iv, num_u, asset, rate, maturity, &strikes, max_strike, &cf_inst,
-            )
+    let asset = asset * pricing.cash_dividend_shift.exp(); //escrowed asset
+    let pricing = &PricingParameters {
+        cash_dividend_shift: 0.0, //asset is already escrowed
+        ..*pricing
+    };
+    check_implied_volatility_mode(sensitivity, iv_mode)?;
+    let results = match sensitivity {
+        Sensitivity::Vega | Sensitivity::Vanna | Sensitivity::Volga => {
+            get_volatility_results(sensitivity, cf_parameters, pricing, asset, strikes)
         }
-        CFParameters::CGMYSE(cf_params) => {
-            let (cf_inst, vol) = get_cgmyse_cf(cf_params, maturity, rate)?;
-            let max_strike = get_max_strike(asset, option_scale, vol);
-            get_option_results(
-                fn_choice, include_iv, num_u, asset, rate, maturity, &strikes, max_strike, &cf_inst,
-            )
+        Sensitivity::Charm => {
+            get_charm_results(option_kind, cf_parameters, pricing, asset, strikes)
         }
-        CFParameters::Merton(cf_params) => {
-            let (cf_inst, vol) = get_merton_cf(cf_params, maturity, rate)?;
-            let max_strike = get_max_strike(asset, option_scale, vol);
-            get_option_results(
-                fn_choice, include_iv, num_u, asset, rate, maturity, &strikes, max_strike, &cf_inst,
-            )
+        Sensitivity::ParameterSensitivities => {
+            get_parameter_sensitivity_results(option_kind, cf_parameters, pricing, asset, strikes)
         }
-        CFParameters::Heston(cf_params) => {
-            let (cf_inst, vol) = get_heston_cf(cf_params, maturity, rate)?;
-            let max_strike = get_max_strike(asset, option_scale, vol);
+        _ => {
+            let (cf_inst, range) = get_model_cf(cf_parameters, pricing)?;
             get_option_results(
-                fn_choice, include_iv, num_u, asset, rate, maturity, &strikes, max_strike, &cf_inst,
+                option_kind,
+                sensitivity,
+                iv_mode,
+                pricing,
+                asset,
+                strikes,
+                get_max_strike(asset, range),
+                &cf_inst,
             )
         }
+    }?;
+    match cf_parameters {
+        CFParameters::BlackScholes(cf_params) => Ok(results
+            .into_iter()
+            .map(|element| GraphElement {
+                closed_form: get_black_scholes_closed_form(
+                    option_kind,
+                    sensitivity,
+                    asset,
+                    element.at_point,
+                    pricing,
+                    cf_params.sigma,
+                ),
+                ..element
+            })
+            .collect()),
+        _ => Ok(results),
     }
 }
 
+/// Returns the selected greeks out of price, delta, gamma
+/// and theta for every strike, evaluating the characteristic
+/// function only once
+pub fn get_greeks_results_as_json(
+    option_kind: OptionKind,
+    selection: &GreeksSelection,
+    cf_parameters: &CFParameters,
+    pricing: &PricingParameters,
+    asset: f64,
+    strikes: &[f64],
+) -> Result<Vec<GreeksElement>, ParameterError> {
+    let asset = asset * pricing.cash_dividend_shift.exp(); //escrowed asset
+    let pricing = &PricingParameters {
+        cash_dividend_shift: 0.0, //asset is already escrowed
+        ..*pricing
+    };
+    let (cf_inst, range) = get_model_cf(cf_parameters, pricing)?;
+    get_greeks_results(
+        option_kind,
+        selection,
+        pricing,
+        asset,
+        strikes,
+        get_max_strike(asset, range),
+        &cf_inst,
+    )
+}
+
 pub fn get_density_results_as_json(
     cf_parameters: &CFParameters,
-    density_scale: f64,
-    num_u: usize,
-    maturity: f64,
-    rate: f64,
+    pricing: &PricingParameters,
 ) -> Result<Vec<GraphElement>, ParameterError> {
-    match cf_parameters {
-        CFParameters::CGMY(cf_params) => {
-            let (cf_inst, vol) = get_cgmy_cf(cf_params, maturity, rate)?;
-            let x_max_density = vol * density_scale;
-            get_density_results(num_u, x_max_density, &cf_inst)
-        }
-        CFParameters::CGMYSE(cf_params) => {
-            let (cf_inst, vol) = get_cgmyse_cf(cf_params, maturity, rate)?;
-            let x_max_density = vol * density_scale;
-            get_density_results(num_u, x_max_density, &cf_inst)
-        }
-        CFParameters::Merton(cf_params) => {
-            let (cf_inst, vol) = get_merton_cf(cf_params, maturity, rate)?;
-            let x_max_density = vol * density_scale;
-            get_density_results(num_u, x_max_density, &cf_inst)
-        }
-        CFParameters::Heston(cf_params) => {
-            let (cf_inst, vol) = get_heston_cf(cf_params, maturity, rate)?;
-            let x_max_density = vol * density_scale;
-            get_density_results(num_u, x_max_density, &cf_inst)
-        }
-    }
+    let (cf_inst, range) = get_model_cf(cf_parameters, pricing)?;
+    Ok(adjust_density(pricing.num_u, range, &cf_inst))
 }
 
 pub fn get_risk_measure_results_as_json(
     cf_parameters: &CFParameters,
-    density_scale: f64,
-    num_u: usize,
-    maturity: f64,
-    rate: f64,
+    pricing: &PricingParameters,
     quantile: f64,
 ) -> Result<cf_dist_utils::RiskMetric, ParameterError> {
-    match cf_parameters {
-        CFParameters::CGMY(cf_params) => {
-            let (cf_inst, vol) = get_cgmy_cf(cf_params, maturity, rate)?;
-            let x_max_density = vol * density_scale;
-            let result = get_risk_measure_results(num_u, x_max_density, quantile, &cf_inst)?;
-            Ok(result)
-        }
-        CFParameters::CGMYSE(cf_params) => {
-            let (cf_inst, vol) = get_cgmyse_cf(cf_params, maturity, rate)?;
-            let x_max_density = vol * density_scale;
-            let result = get_risk_measure_results(num_u, x_max_density, quantile, &cf_inst)?;
-            Ok(result)
-        }
-        CFParameters::Merton(cf_params) => {
-            let (cf_inst, vol) = get_merton_cf(cf_params, maturity, rate)?;
-            let x_max_density = vol * density_scale;
-            let result = get_risk_measure_results(num_u, x_max_density, quantile, &cf_inst)?;
-            Ok(result)
-        }
-        CFParameters::Heston(cf_params) => {
-            let (cf_inst, vol) = get_heston_cf(cf_params, maturity, rate)?;
-            let x_max_density = vol * density_scale;
-            let result = get_risk_measure_results(num_u, x_max_density, quantile, &cf_inst)?;
-            Ok(result)
+    let (cf_inst, range) = get_model_cf(cf_parameters, pricing)?;
+    Ok(get_risk_measure_results(
+        pricing.num_u,
+        range,
+        quantile,
+        &cf_inst,
+    )?)
+}
+
+/// Returns the truncation range and the characteristic
+/// function of the log return on its "u" grid
+fn get_distribution_cf(
+    cf_parameters: &CFParameters,
+    pricing: &PricingParameters,
+) -> Result<(TruncationRange, Vec<Complex<f64>>), ParameterError> {
+    let (cf_inst, (x_min, x_max)) = get_model_cf(cf_parameters, pricing)?;
+    Ok((
+        (x_min, x_max),
+        fang_oost::get_discrete_cf(pricing.num_u, x_min, x_max, &cf_inst),
+    ))
+}
+
+/// A point of the distribution of the log return, along
+/// with the asset price at that log return
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DistributionElement {
+    pub log_return: f64,
+    #[serde(skip_serializing_if = "Option::is_none")] //only provided with an asset
+    pub price: Option<f64>,
+    pub probability: f64,
+}
+
+/// Returns log returns from either log returns or prices,
+/// where prices require the asset
+pub fn get_log_returns(
+    log_returns: Option<&[f64]>,
+    prices: Option<&[f64]>,
+    asset: Option<f64>,
+) -> Result<Vec<f64>, ParameterError> {
+    match (log_returns, prices) {
+        (Some(log_returns), _) => Ok(log_returns.to_vec()),
+        (None, Some(prices)) => {
+            let asset = asset.ok_or_else(|| throw_no_exist_error("asset"))?;
+            Ok(prices.iter().map(|price| (price / asset).ln()).collect())
         }
+        (None, None) => Err(throw_no_exist_error("log_returns")),
     }
 }
 
+/// Returns the cumulative probability of the log return at
+/// each point.  Points outside of the truncation range have
+/// a probability of zero or one.
+pub fn get_cdf_results_as_json(
+    cf_parameters: &CFParameters,
+    pricing: &PricingParameters,
+    asset: Option<f64>,
+    log_returns: &[f64],
+) -> Result<Vec<DistributionElement>, ParameterError> {
+    let ((x_min, x_max), discrete_cf) = get_distribution_cf(cf_parameters, pricing)?;
+    Ok(log_returns
+        .iter()
+        .map(|log_return| DistributionElement {
+            log_return: *log_return,
+            price: asset.map(|asset| asset * log_return.exp()),
+            probability: match *log_return {
+                x if x <= x_min => 0.0,
+                x if x >= x_max => 1.0,
+                x => cf_dist_utils::get_cdf_discrete_cf(x, x_min, x_max, &discrete_cf)
+                    .clamp(0.0, 1.0),
+            },
+        })
+        .collect())
+}
+
+/// Returns the log return at each probability, by finding
+/// the root of the cumulative distribution function
+pub fn get_quantile_results_as_json(
+    cf_parameters: &CFParameters,
+    pricing: &PricingParameters,
+    asset: Option<f64>,
+    probabilities: &[f64],
+) -> Result<Vec<DistributionElement>, ParameterError> {
+    let ((x_min, x_max), discrete_cf) = get_distribution_cf(cf_parameters, pricing)?;
+    probabilities
+        .iter()
+        .map(|probability| {
+            //value at risk is the negative of the quantile
+            let cf_dist_utils::RiskMetric { value_at_risk, .. } =
+                cf_dist_utils::get_expected_shortfall_and_value_at_risk_discrete_cf(
+                    *probability,
+                    x_min,
+                    x_max,
+                    MAX_SIMS,
+                    PRECISION,
+                    &discrete_cf,
+                )?;
+            Ok(DistributionElement {
+                log_return: -value_at_risk,
+                price: asset.map(|asset| asset * (-value_at_risk).exp()),
+                probability: *probability,
+            })
+        })
+        .collect()
+}
+
+/// Reason the implied volatility does not exist at a strike
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImpliedVolatilityError {
+    BelowIntrinsic,
+    AboveUpperBound,
+    NoRoot,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GraphElement {
     pub at_point: f64,
     pub value: f64,
     #[serde(skip_serializing_if = "Option::is_none")] //skip when iv is not provided
     pub iv: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")] //why iv could not be found
+    pub iv_error: Option<ImpliedVolatilityError>,
+    #[serde(skip_serializing_if = "Option::is_none")] //only provided for black scholes
+    pub closed_form: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")] //only provided with a rate curve
+    pub effective_rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")] //keyed by the model's parameter names
+    pub parameter_sensitivities: Option<BTreeMap<String, f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")] //only provided when num_u is auto
+    pub convergence_error: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")] //"sigma" or "v0" for vega, vanna and volga
+    pub volatility_parameter: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GreeksElement {
+    pub at_point: f64,
+    #[serde(skip_serializing_if = "Option::is_none")] //skip when not selected
+    pub price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gamma: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theta: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")] //skip when iv is not provided
+    pub iv: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")] //why iv could not be found
+    pub iv_error: Option<ImpliedVolatilityError>,
+    #[serde(skip_serializing_if = "Option::is_none")] //only provided with a rate curve
+    pub effective_rate: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct RiskMetricResponse {
+    #[serde(flatten)]
+    pub risk_metric: cf_dist_utils::RiskMetric,
+    #[serde(skip_serializing_if = "Option::is_none")] //only provided with a rate curve
+    pub effective_rate: Option<f64>,
+}
+
+/// Call prices and implied volatilities, with one row per
+/// maturity and one column per strike
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SurfaceResponse {
+    pub maturities: Vec<f64>,
+    pub strikes: Vec<Vec<f64>>, //moneyness grids give different strikes per maturity
+    pub prices: Vec<Vec<f64>>,
+    pub implied_volatilities: Vec<Vec<f64>>, //null where the inversion fails
+    pub implied_volatility_errors: Vec<Vec<Option<ImpliedVolatilityError>>>, //null on success
+    #[serde(skip_serializing_if = "Option::is_none")] //only provided with a rate curve
+    pub effective_rates: Option<Vec<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")] //only provided when requested
+    pub arbitrage_violations: Option<Vec<ArbitrageViolation>>,
+}
+
+/// Returns call prices and implied volatilities for every
+/// maturity, evaluating the maturities in parallel.  Rates,
+/// dividends and moneyness are resolved per maturity.
+/// Optionally checks the surface for static arbitrage.
+pub fn get_surface_results_as_json(
+    parameters: &SurfaceParameters,
+    option_scale: f64,
+    num_u: usize,
+    check_arbitrage: bool,
+) -> Result<SurfaceResponse, ParameterError> {
+    let SurfaceParameters {
+        maturities,
+        rates,
+        asset,
+        strikes,
+        moneyness,
+        cf_parameters,
+        ..
+    } = parameters;
+    let slices = maturities
+        .par_iter()
+        .map(|maturity| {
+            let (pricing, effective_rate) =
+                get_pricing_parameters(rates, Some(*asset), *maturity, num_u, option_scale)?;
+            let forward = pricing.forward(*asset);
+            let slice_strikes = match (strikes, moneyness) {
+                (Some(strikes), _) => strikes.clone(),
+                (None, Some(moneyness)) => moneyness.iter().map(|m| m * forward).collect(),
+                (None, None) => return Err(throw_no_exist_error("strikes")),
+            };
+            let results = get_option_results_as_json(
+                OptionKind::Call,
+                Sensitivity::Price,
+                ImpliedVolatilityMode::Lenient,
+                cf_parameters,
+                &pricing,
+                *asset,
+                &slice_strikes,
+            )?;
+            Ok((slice_strikes, results, pricing, effective_rate))
+        })
+        .collect::<Result<Vec<_>, ParameterError>>()?;
+    let mut response = SurfaceResponse {
+        maturities: maturities.clone(),
+        strikes: Vec::with_capacity(slices.len()),
+        prices: Vec::with_capacity(slices.len()),
+        implied_volatilities: Vec::with_capacity(slices.len()),
+        implied_volatility_errors: Vec::with_capacity(slices.len()),
+        effective_rates: rates.rate_curve.as_ref().map(|_| vec![]),
+        arbitrage_violations: None,
+    };
+    let mut forwards = Vec::with_capacity(slices.len());
+    let mut discounts = Vec::with_capacity(slices.len());
+    for (slice_strikes, results, pricing, effective_rate) in slices {
+        forwards.push(pricing.forward(*asset));
+        discounts.push(pricing.discount());
+        response.strikes.push(slice_strikes);
+        response
+            .prices
+            .push(results.iter().map(|element| element.value).collect());
+        response.implied_volatilities.push(
+            results
+                .iter()
+                .map(|element| element.iv.unwrap_or(f64::NAN))
+                .collect(),
+        );
+        response
+            .implied_volatility_errors
+            .push(results.iter().map(|element| element.iv_error).collect());
+        if let (Some(effective_rates), Some(rate)) =
+            (response.effective_rates.as_mut(), effective_rate)
+        {
+            effective_rates.push(rate);
+        }
+    }
+    if check_arbitrage {
+        let mut violations = vec![];
+        for (index, (slice_strikes, prices)) in response
+            .strikes
+            .iter()
+            .zip(response.prices.iter())
+            .enumerate()
+        {
+            violations.extend(arbitrage::get_strike_violations(
+                slice_strikes,
+                prices,
+                forwards[index],
+                discounts[index],
+            ));
+        }
+        violations.extend(arbitrage::get_calendar_violations(
+            maturities,
+            &response.strikes,
+            &response.prices,
+            &forwards,
+            &discounts,
+        ));
+        response.arbitrage_violations = Some(violations);
+    }
+    Ok(response)
+}
+
+/// Prices calls at the strikes of a single maturity and
+/// returns the static arbitrage conditions they violate.
+/// Put prices at the same strikes, when provided, are also
+/// checked against put-call parity.
+pub fn get_arbitrage_violations_as_json(
+    cf_parameters: &CFParameters,
+    pricing: &PricingParameters,
+    asset: f64,
+    strikes: &[f64],
+    puts: Option<&[f64]>,
+) -> Result<Vec<ArbitrageViolation>, ParameterError> {
+    let results = get_option_results_as_json(
+        OptionKind::Call,
+        Sensitivity::Price,
+        ImpliedVolatilityMode::Skip,
+        cf_parameters,
+        pricing,
+        asset,
+        strikes,
+    )?;
+    let prices: Vec<f64> = results.iter().map(|element| element.value).collect();
+    let forward = pricing.forward(asset);
+    let discount = pricing.discount();
+    let mut violations = arbitrage::get_strike_violations(strikes, &prices, forward, discount);
+    if let Some(puts) = puts {
+        violations.extend(arbitrage::get_put_call_parity_violations(
+            strikes, &prices, puts, forward, discount,
+        ));
+    }
+    Ok(violations)
+}
+
+/// Response of the calculator route when num_u is "auto" or
+/// arbitrage is checked.  With num_u "auto" the results come
+/// from the smallest "u" grid whose values change by less
+/// than the tolerance when the grid is halved, and otherwise
+/// from the requested grid.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CalculatorResponse {
+    pub results: Vec<GraphElement>,
+    pub num_u: usize,    //exponent, so 8 means 2^8=256 discrete "u"
+    pub converged: bool, //false when the largest num_u is reached first
+    #[serde(skip_serializing_if = "Option::is_none")] //only when check_arbitrage is true
+    pub arbitrage_violations: Option<Vec<ArbitrageViolation>>,
+}
+
+/// The calculator route keeps returning the bare results
+/// unless num_u is "auto" or arbitrage is checked
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum CalculatorOutput {
+    Results(Vec<GraphElement>),
+    Response(CalculatorResponse),
+}
+
+/// Doubles the number of discrete "u", starting from the lower
+/// bound on num_u, until every value changes by less than the
+/// tolerance or the upper bound is reached.  The last change at
+/// each strike is reported as its convergence error.  The
+/// num_u of `pricing` is ignored.  Implied volatilities are
+/// left to `get_implied_volatility_results`, so that they are
+/// found from the final values without pricing again.
+pub fn get_adaptive_option_results_as_json(
+    option_kind: OptionKind,
+    sensitivity: Sensitivity,
+    cf_parameters: &CFParameters,
+    pricing: &PricingParameters,
+    tolerance: f64,
+    asset: f64,
+    strikes: &[f64],
+) -> Result<CalculatorResponse, ParameterError> {
+    let get_results = |num_u: usize| {
+        get_option_results_as_json(
+            option_kind,
+            sensitivity,
+            ImpliedVolatilityMode::Skip,
+            cf_parameters,
+            &PricingParameters {
+                num_u: 2_usize.pow(num_u as u32),
+                ..*pricing
+            },
+            asset,
+            strikes,
+        )
+    };
+    let max_num_u = PARAMETER_CONSTRAINTS.num_u.upper as usize;
+    let mut num_u = PARAMETER_CONSTRAINTS.num_u.lower as usize;
+    let mut previous = get_results(num_u)?;
+    loop {
+        num_u += 1;
+        let current = get_results(num_u)?;
+        let errors: Vec<f64> = current
+            .iter()
+            .zip(previous.iter())
+            .map(|(current, previous)| (current.value - previous.value).abs())
+            .collect();
+        let converged = errors.iter().all(|error| *error < tolerance);
+        if converged || num_u >= max_num_u {
+            return Ok(CalculatorResponse {
+                results: current
+                    .into_iter()
+                    .zip(errors)
+                    .map(|(element, error)| GraphElement {
+                        convergence_error: Some(error),
+                        ..element
+                    })
+                    .collect(),
+                num_u,
+                converged,
+                arbitrage_violations: None,
+            });
+        }
+        previous = current;
+    }
+}
+
+/// Adds implied volatilities to prices that are already
+/// computed, eg by `get_adaptive_option_results_as_json`.
+/// Only prices have an implied volatility, as in
+/// `get_option_results_as_json`.
+pub fn get_implied_volatility_results(
+    option_kind: OptionKind,
+    sensitivity: Sensitivity,
+    iv_mode: ImpliedVolatilityMode,
+    pricing: &PricingParameters,
+    asset: f64,
+    results: Vec<GraphElement>,
+) -> Result<Vec<GraphElement>, ParameterError> {
+    check_implied_volatility_mode(sensitivity, iv_mode)?;
+    if iv_mode == ImpliedVolatilityMode::Skip || sensitivity != Sensitivity::Price {
+        return Ok(results);
+    }
+    let PricingParameters {
+        maturity,
+        rate,
+        dividend_yield,
+        cash_dividend_shift,
+        ..
+    } = *pricing;
+    let strict_iv = iv_mode == ImpliedVolatilityMode::Strict;
+    //escrowed asset, net of the dividend yield
+    let adjusted_asset = asset * (cash_dividend_shift - dividend_yield * maturity).exp();
+    let prices = results.par_iter().map(|element| fang_oost::GraphElement {
+        x: element.at_point,
+        value: element.value,
+    });
+    let ivs = match option_kind {
+        OptionKind::Call => call_iv_as_json(prices, strict_iv, adjusted_asset, rate, maturity),
+        OptionKind::Put => put_iv_as_json(prices, strict_iv, adjusted_asset, rate, maturity),
+    }?;
+    Ok(results
+        .into_iter()
+        .zip(ivs)
+        .map(
+            |(element, GraphElement { iv, iv_error, .. })| GraphElement {
+                iv,
+                iv_error,
+                ..element
+            },
+        )
+        .collect())
+}
+
+/// Reports the rate derived from a curve on each element
+pub fn with_effective_rate(
+    results: Vec<GraphElement>,
+    effective_rate: Option<f64>,
+) -> Vec<GraphElement> {
+    results
+        .into_iter()
+        .map(|element| GraphElement {
+            effective_rate,
+            ..element
+        })
+        .collect()
 }
 
 fn density_as_json(
@@ -263,6 +1217,12 @@ fn density_as_json(
             at_point: x,
             value,
             iv: None,
+            iv_error: None,
+            closed_form: None,
+            effective_rate: None,
+            parameter_sensitivities: None,
+            convergence_error: None,
+            volatility_parameter: None,
         })
         .collect::<Vec<_>>()
 }
@@ -275,66 +1235,115 @@ fn graph_no_iv_as_json(
             at_point: x,
             value,
             iv: None,
+            iv_error: None,
+            closed_form: None,
+            effective_rate: None,
+            parameter_sensitivities: None,
+            convergence_error: None,
+            volatility_parameter: None,
         })
         .collect::<Vec<_>>()
 }
-fn graph_iv_as_json<T>(
+/// Classifies prices outside the no-arbitrage bounds of
+/// the option before searching for the implied volatility.
+/// In strict mode any failure fails the whole request.
+fn graph_iv_as_json<T, U>(
     values: impl IndexedParallelIterator<Item = fang_oost::GraphElement>,
+    strict_iv: bool,
+    price_bounds: U,
     iv_fn: T,
 ) -> Result<Vec<GraphElement>, ParameterError>
 where
     T: Fn(f64, f64) -> Result<f64, f64> + std::marker::Sync + std::marker::Send,
+    U: Fn(f64) -> (f64, f64) + std::marker::Sync + std::marker::Send,
 {
     values
         .map(|fang_oost::GraphElement { x, value }| {
-            iv_fn(value, x)
-                .map(|iv| GraphElement {
+            let (lower, upper) = price_bounds(x);
+            let iv = if value <= lower {
+                Err(ImpliedVolatilityError::BelowIntrinsic)
+            } else if value >= upper {
+                Err(ImpliedVolatilityError::AboveUpperBound)
+            } else {
+                iv_fn(value, x).map_err(|_err| ImpliedVolatilityError::NoRoot)
+            };
+            match iv {
+                Err(_) if strict_iv => Err(throw_no_convergence_error()),
+                iv => Ok(GraphElement {
                     at_point: x,
                     value,
-                    iv: Some(iv),
-                })
-                .map_err(|_err| throw_no_convergence_error())
+                    iv: iv.ok(),
+                    iv_error: iv.err(),
+                    closed_form: None,
+                    effective_rate: None,
+                    parameter_sensitivities: None,
+                    convergence_error: None,
+                    volatility_parameter: None,
+                }),
+            }
         })
         .collect()
 }
 
 fn call_iv_as_json(
     values: impl IndexedParallelIterator<Item = fang_oost::GraphElement>,
+    strict_iv: bool,
     asset: f64,
     rate: f64,
     maturity: f64,
 ) -> Result<Vec<GraphElement>, ParameterError> {
-    graph_iv_as_json(values, &|price, strike| {
-        black_scholes::call_iv(price, asset, strike, rate, maturity)
-    })
+    let discount = (-rate * maturity).exp();
+    graph_iv_as_json(
+        values,
+        strict_iv,
+        |strike| ((asset - strike * discount).max(0.0), asset),
+        |price, strike| black_scholes::call_iv(price, asset, strike, rate, maturity),
+    )
 }
 fn put_iv_as_json(
     values: impl IndexedParallelIterator<Item = fang_oost::GraphElement>,
+    strict_iv: bool,
     asset: f64,
     rate: f64,
     maturity: f64,
 ) -> Result<Vec<GraphElement>, ParameterError> {
-    graph_iv_as_json(values, &|price, strike| {
-        black_scholes::put_iv(price, asset, strike, rate, maturity)
-    })
+    let discount = (-rate * maturity).exp();
+    graph_iv_as_json(
+        values,
+        strict_iv,
+        |strike| ((strike * discount - asset).max(0.0), strike * discount),
+        |price, strike| black_scholes::put_iv(price, asset, strike, rate, maturity),
+    )
 }
 
 const NUM_X: usize = 128;
-fn adjust_density<T>(num_u: usize, x_max: f64, cf: T) -> Vec<GraphElement>
+fn adjust_density<T>(num_u: usize, (x_min, x_max): TruncationRange, cf: T) -> Vec<GraphElement>
 where
     T: Fn(&Complex<f64>) -> Complex<f64> + std::marker::Sync + std::marker::Send,
 {
-    let x_min = -x_max;
     density_as_json(cf_dist_utils::get_pdf(NUM_X, num_u, x_min, x_max, &cf))
 }
 
+/// Shifts call values from the library, which derives
+/// calls from puts by put-call parity without dividends
+fn adjust_call_for_dividends(
+    values: impl IndexedParallelIterator<Item = fang_oost::GraphElement>,
+    adjustment: f64,
+) -> impl IndexedParallelIterator<Item = fang_oost::GraphElement> {
+    values.map(
+        move |fang_oost::GraphElement { x, value }| fang_oost::GraphElement {
+            x,
+            value: value + adjustment,
+        },
+    )
+}
+
 fn get_option_results<S>(
-    fn_choice: i32,
-    include_iv: bool,
-    num_u: usize,
+    option_kind: OptionKind,
+    sensitivity: Sensitivity,
+    iv_mode: ImpliedVolatilityMode,
+    pricing: &PricingParameters,
     asset: f64,
-    rate: f64,
-    maturity: f64,
     strikes: &[f64],
     max_strike: f64,
     inst_cf: S,
@@ -342,76 +1351,859 @@ fn get_option_results<S>(
 where
     S: Fn(&Complex<f64>) -> Complex<f64> + std::marker::Sync + std::marker::Send,
 {
-    match fn_choice {
-        CALL_PRICE => {
-            let prices = option_pricing::fang_oost_call_price(
-                num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
+    let PricingParameters {
+        num_u,
+        maturity,
+        rate,
+        dividend_yield,
+        ..
+    } = *pricing;
+    let strict_iv = iv_mode == ImpliedVolatilityMode::Strict;
+    let dividend_discount = (-dividend_yield * maturity).exp();
+    let adjusted_asset = asset * dividend_discount;
+    match (option_kind, sensitivity) {
+        (OptionKind::Call, Sensitivity::Price) => {
+            let prices = adjust_call_for_dividends(
+                option_pricing::fang_oost_call_price(
+                    num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
+                ),
+                adjusted_asset - asset,
             );
-            if include_iv {
-                call_iv_as_json(prices, asset, rate, maturity)
-            } else {
-                Ok(graph_no_iv_as_json(prices))
+            match iv_mode {
+                ImpliedVolatilityMode::Skip => Ok(graph_no_iv_as_json(prices)),
+                _ => call_iv_as_json(prices, strict_iv, adjusted_asset, rate, maturity),
             }
         }
-        PUT_PRICE => {
+        (OptionKind::Put, Sensitivity::Price) => {
             let prices = option_pricing::fang_oost_put_price(
                 num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
             );
-            if include_iv {
-                put_iv_as_json(prices, asset, rate, maturity)
-            } else {
-                Ok(graph_no_iv_as_json(prices))
+            match iv_mode {
+                ImpliedVolatilityMode::Skip => Ok(graph_no_iv_as_json(prices)),
+                _ => put_iv_as_json(prices, strict_iv, adjusted_asset, rate, maturity),
             }
         }
-        CALL_DELTA => Ok(graph_no_iv_as_json(option_pricing::fang_oost_call_delta(
-            num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
-        ))),
-        PUT_DELTA => Ok(graph_no_iv_as_json(option_pricing::fang_oost_put_delta(
-            num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
-        ))),
-        CALL_GAMMA => Ok(graph_no_iv_as_json(option_pricing::fang_oost_call_gamma(
-            num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
-        ))),
-        PUT_GAMMA => Ok(graph_no_iv_as_json(option_pricing::fang_oost_put_gamma(
-            num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
-        ))),
-        CALL_THETA => Ok(graph_no_iv_as_json(option_pricing::fang_oost_call_theta(
-            num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
-        ))),
-        PUT_THETA => Ok(graph_no_iv_as_json(option_pricing::fang_oost_put_theta(
-            num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
-        ))),
-        _ => Err(ParameterError::new(&ErrorType::FunctionError(format!(
-            "{}",
-            fn_choice
+        (OptionKind::Call, Sensitivity::Delta) => {
+            Ok(graph_no_iv_as_json(adjust_call_for_dividends(
+                option_pricing::fang_oost_call_delta(
+                    num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
+                ),
+                dividend_discount - 1.0,
+            )))
+        }
+        (OptionKind::Put, Sensitivity::Delta) => {
+            Ok(graph_no_iv_as_json(option_pricing::fang_oost_put_delta(
+                num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
+            )))
+        }
+        (OptionKind::Call, Sensitivity::Gamma) => {
+            Ok(graph_no_iv_as_json(option_pricing::fang_oost_call_gamma(
+                num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
+            )))
+        }
+        (OptionKind::Put, Sensitivity::Gamma) => {
+            Ok(graph_no_iv_as_json(option_pricing::fang_oost_put_gamma(
+                num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
+            )))
+        }
+        //shares the theta transform of the greeks route
+        (_, Sensitivity::Theta) => Ok(get_greeks_results(
+            option_kind,
+            &GreeksSelection {
+                sensitivities: vec![Sensitivity::Theta],
+                iv_mode: ImpliedVolatilityMode::Skip,
+            },
+            pricing,
+            asset,
+            strikes,
+            max_strike,
+            &inst_cf,
+        )?
+        .into_iter()
+        .map(|element| GraphElement {
+            at_point: element.at_point,
+            value: element.theta.unwrap_or_default(), //always selected
+            iv: None,
+            iv_error: None,
+            closed_form: None,
+            effective_rate: None,
+            parameter_sensitivities: None,
+            convergence_error: None,
+            volatility_parameter: None,
+        })
+        .collect()),
+        (_, Sensitivity::Rho) => Ok(get_rho_results(
+            option_kind,
+            pricing,
+            asset,
+            strikes,
+            max_strike,
+            &inst_cf,
+        )),
+        (_, Sensitivity::Speed) => {
+            //third derivative in the log asset, moved to the asset
+            let asset_cubed = asset.powi(3);
+            Ok(graph_no_iv_as_json(
+                option_pricing::fang_oost_put_price(
+                    num_u,
+                    asset,
+                    &strikes,
+                    max_strike,
+                    rate,
+                    maturity,
+                    |u: &Complex<f64>| inst_cf(u) * u * (u - 1.0) * (u - 2.0),
+                )
+                .map(move |fang_oost::GraphElement { x, value }| {
+                    fang_oost::GraphElement {
+                        x,
+                        value: value / asset_cubed,
+                    }
+                }),
+            ))
+        }
+        //these need the model parameters, see get_option_results_as_json
+        (
+            _,
+            Sensitivity::Vega
+            | Sensitivity::Vanna
+            | Sensitivity::Volga
+            | Sensitivity::Charm
+            | Sensitivity::ParameterSensitivities,
+        ) => Err(ParameterError::new(&ErrorType::FunctionError(format!(
+            "{:?}",
+            sensitivity
         )))),
     }
 }
 
-fn get_density_results(
-    num_u: usize,
-    x_max_density: f64,
-    inst_cf: &(impl Fn(&Complex<f64>) -> Complex<f64> + std::marker::Sync),
-) -> Result<Vec<GraphElement>, ParameterError> {
-    Ok(adjust_density(num_u, x_max_density, &inst_cf))
+/// Sensitivities available from the greeks route, which
+/// share one discretisation of the characteristic function
+const GREEKS: [Sensitivity; 4] = [
+    Sensitivity::Price,
+    Sensitivity::Delta,
+    Sensitivity::Gamma,
+    Sensitivity::Theta,
+];
+
+/// Greeks and implied volatilities to return from the
+/// greeks route
+#[derive(Clone, Debug, PartialEq)]
+pub struct GreeksSelection {
+    pub sensitivities: Vec<Sensitivity>,
+    pub iv_mode: ImpliedVolatilityMode,
 }
-const MAX_SIMS: usize = 100;
-const PRECISION: f64 = 0.0000001;
 
-fn get_risk_measure_results(
-    num_u: usize,
-    x_max_density: f64,
-    quantile: f64,
+impl GreeksSelection {
+    /// Parses a comma separated list of greeks, defaulting
+    /// to all of them
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate utils;
+    /// use utils::pricing_maps::{GreeksSelection, ImpliedVolatilityMode, Sensitivity};
+    /// # fn main() {
+    /// let selection = GreeksSelection::new(Some("delta,gamma"), ImpliedVolatilityMode::Skip).unwrap();
+    /// assert_eq!(selection.sensitivities, vec![Sensitivity::Delta, Sensitivity::Gamma]);
+    /// # }
+    /// ```
+    pub fn new(
+        sensitivities: Option<&str>,
+        iv_mode: ImpliedVolatilityMode,
+    ) -> Result<Self, ParameterError> {
+        let sensitivities = match sensitivities {
+            None => GREEKS.to_vec(),
+            Some(sensitivities) => sensitivities
+                .split(',')
+                .map(
+                    |sensitivity| match Sensitivity::from_param(sensitivity.trim()) {
+                        Ok(sensitivity) if GREEKS.contains(&sensitivity) => Ok(sensitivity),
+                        _ => Err(ParameterError::new(&ErrorType::FunctionError(
+                            sensitivity.to_string(),
+                        ))),
+                    },
+                )
+                .collect::<Result<Vec<_>, ParameterError>>()?,
+        };
+        Ok(GreeksSelection {
+            sensitivities,
+            iv_mode,
+        })
+    }
+    fn is_selected(&self, sensitivity: Sensitivity) -> bool {
+        self.sensitivities.contains(&sensitivity)
+    }
+}
+
+/// Transform of the characteristic function at `u` that
+/// differentiates the option in the log asset or in time
+type GreekTransform<'a> = dyn Fn(&Complex<f64>, &Complex<f64>) -> Complex<f64> + Sync + 'a;
+
+/// Minus the derivative in maturity of the discounted
+/// characteristic function.  The log of the characteristic
+/// function over maturity is the Levy exponent, so theta is
+/// only exact for Levy processes without a time change.
+fn theta_transform(cf: &Complex<f64>, maturity: f64, rate: f64) -> Complex<f64> {
+    if cf.re > 0.0 {
+        -(cf.ln() / maturity - rate) * cf
+    } else {
+        Complex::new(0.0, 0.0)
+    }
+}
+
+//payoff coefficients of a put, as in fang_oost_option
+fn chi_k(a: f64, c: f64, d: f64, u: f64) -> f64 {
+    let iter_s = |x| u * (x - a);
+    let exp_d = d.exp();
+    let exp_c = c.exp();
+    (iter_s(d).cos() * exp_d - iter_s(c).cos() * exp_c + u * iter_s(d).sin() * exp_d
+        - u * iter_s(c).sin() * exp_c)
+        / (1.0 + u * u)
+}
+fn phi_k(a: f64, c: f64, d: f64, u: f64, k: usize) -> f64 {
+    let iter_s = |x| u * (x - a);
+    if k == 0 {
+        d - c
+    } else {
+        (iter_s(d).sin() - iter_s(c).sin()) / u
+    }
+}
+
+/// Returns the selected greeks for every strike.  The
+/// characteristic function is evaluated once on the `u`
+/// grid, and each greek transforms that vector before
+/// integrating against the put payoff, using the same
+/// range and transforms as `fang_oost_option`.  Prices are
+/// computed whenever implied volatilities are requested.
+fn get_greeks_results<S>(
+    option_kind: OptionKind,
+    selection: &GreeksSelection,
+    pricing: &PricingParameters,
+    asset: f64,
+    strikes: &[f64],
+    max_strike: f64,
+    inst_cf: S,
+) -> Result<Vec<GreeksElement>, ParameterError>
+where
+    S: Fn(&Complex<f64>) -> Complex<f64> + std::marker::Sync + std::marker::Send,
+{
+    let PricingParameters {
+        num_u,
+        maturity,
+        rate,
+        dividend_yield,
+        ..
+    } = *pricing;
+    let discount = pricing.discount();
+    let dividend_discount = (-dividend_yield * maturity).exp();
+    let adjusted_asset = asset * dividend_discount;
+    let x_min = (asset / max_strike).ln();
+    let x_max = -x_min;
+    let u_domain: Vec<Complex<f64>> = fang_oost::get_u_domain(num_u, x_min, x_max).collect();
+    let discrete_cf: Vec<Complex<f64>> = u_domain.par_iter().map(&inst_cf).collect();
+    //undiscounted put expectation per unit strike
+    let get_values = |transform: &GreekTransform<'_>| {
+        let transformed_cf: Vec<Complex<f64>> = discrete_cf
+            .iter()
+            .zip(&u_domain)
+            .map(|(cf, u)| transform(cf, u))
+            .collect();
+        fang_oost::get_expectation_extended(
+            x_min,
+            x_max,
+            strikes.par_iter().map(|strike| (asset / strike).ln()),
+            &transformed_cf,
+            |u, _, k| phi_k(x_min, x_min, 0.0, u, k) - chi_k(x_min, x_min, 0.0, u),
+        )
+        .map(|fang_oost::GraphElement { value, .. }| value)
+        .collect::<Vec<f64>>()
+    };
+    let GreeksSelection { iv_mode, .. } = *selection;
+    let is_selected = |sensitivity: Sensitivity| selection.is_selected(sensitivity);
+    let include_price = is_selected(Sensitivity::Price) || iv_mode != ImpliedVolatilityMode::Skip;
+    let prices = if include_price {
+        let puts = get_values(&|cf, _| *cf);
+        let prices = strikes.par_iter().zip(puts).map(|(strike, value)| {
+            let put = value * discount * strike;
+            fang_oost::GraphElement {
+                x: *strike,
+                value: match option_kind {
+                    OptionKind::Call => put + adjusted_asset - discount * strike,
+                    OptionKind::Put => put,
+                },
+            }
+        });
+        let strict_iv = iv_mode == ImpliedVolatilityMode::Strict;
+        Some(match (option_kind, iv_mode) {
+            (_, ImpliedVolatilityMode::Skip) => graph_no_iv_as_json(prices),
+            (OptionKind::Call, _) => {
+                call_iv_as_json(prices, strict_iv, adjusted_asset, rate, maturity)?
+            }
+            (OptionKind::Put, _) => {
+                put_iv_as_json(prices, strict_iv, adjusted_asset, rate, maturity)?
+            }
+        })
+    } else {
+        None
+    };
+    let get_greek = |sensitivity: Sensitivity,
+                     transform: &GreekTransform<'_>,
+                     output: &dyn Fn(f64, f64) -> f64| {
+        if is_selected(sensitivity) {
+            Some(
+                get_values(transform)
+                    .into_iter()
+                    .zip(strikes)
+                    .map(|(value, strike)| output(value * discount * strike, *strike))
+                    .collect::<Vec<f64>>(),
+            )
+        } else {
+            None
+        }
+    };
+    let call_delta_shift = match option_kind {
+        OptionKind::Call => dividend_discount,
+        OptionKind::Put => 0.0,
+    };
+    let deltas = get_greek(Sensitivity::Delta, &|cf, u| cf * u, &|put, _| {
+        put / asset + call_delta_shift
+    });
+    let gammas = get_greek(
+        Sensitivity::Gamma,
+        &|cf, u| -cf * u * (1.0 - u),
+        &|put, _| put / asset.powi(2),
+    );
+    let thetas = get_greek(
+        Sensitivity::Theta,
+        &|cf, _| theta_transform(cf, maturity, rate),
+        &|put, strike| match option_kind {
+            OptionKind::Call => put - rate * discount * strike + dividend_yield * adjusted_asset,
+            OptionKind::Put => put,
+        },
+    );
+    let get_value = |values: &Option<Vec<f64>>, index: usize| values.as_ref().map(|v| v[index]);
+    Ok(strikes
+        .iter()
+        .enumerate()
+        .map(|(index, strike)| {
+            let price = prices.as_ref().map(|prices| &prices[index]);
+            GreeksElement {
+                at_point: *strike,
+                price: price
+                    .filter(|_| is_selected(Sensitivity::Price))
+                    .map(|price| price.value),
+                delta: get_value(&deltas, index),
+                gamma: get_value(&gammas, index),
+                theta: get_value(&thetas, index),
+                iv: price.and_then(|price| price.iv),
+                iv_error: price.and_then(|price| price.iv_error),
+                effective_rate: None,
+            }
+        })
+        .collect())
+}
+
+/// Returns rho.  The rate only enters the characteristic
+/// function through the drift, so its derivative is the
+/// characteristic function times `maturity*u`.  Puts also
+/// lose `maturity` times their value through discounting,
+/// and calls follow from put-call parity.  Cash dividends
+/// are held fixed.
+fn get_rho_results<S>(
+    option_kind: OptionKind,
+    pricing: &PricingParameters,
+    asset: f64,
+    strikes: &[f64],
+    max_strike: f64,
+    inst_cf: S,
+) -> Vec<GraphElement>
+where
+    S: Fn(&Complex<f64>) -> Complex<f64> + std::marker::Sync + std::marker::Send,
+{
+    let PricingParameters {
+        num_u,
+        maturity,
+        rate,
+        ..
+    } = *pricing;
+    let call_adjustment = match option_kind {
+        OptionKind::Call => maturity * (-rate * maturity).exp(),
+        OptionKind::Put => 0.0,
+    };
+    let prices = option_pricing::fang_oost_put_price(
+        num_u, asset, strikes, max_strike, rate, maturity, &inst_cf,
+    );
+    let drift_sensitivities = option_pricing::fang_oost_put_price(
+        num_u,
+        asset,
+        strikes,
+        max_strike,
+        rate,
+        maturity,
+        |u: &Complex<f64>| maturity * u * inst_cf(u),
+    );
+    graph_no_iv_as_json(
+        prices
+            .zip(drift_sensitivities)
+            .map(move |(price, drift_sensitivity)| fang_oost::GraphElement {
+                x: price.x,
+                value: drift_sensitivity.value - maturity * price.value + call_adjustment * price.x,
+            }),
+    )
+}
+
+const PARAMETER_BUMP: f64 = 0.0001;
+
+/// Returns the derivative of `sensitivity` with respect
+/// to the named model parameter by central differences.
+/// The bump is relative for parameters larger than one,
+/// and is one sided at the parameter bounds.
+fn get_bumped_results(
+    option_kind: OptionKind,
+    sensitivity: Sensitivity,
+    cf_parameters: &CFParameters,
+    parameter: &str,
+    pricing: &PricingParameters,
+    asset: f64,
+    strikes: &[f64],
+) -> Result<Vec<GraphElement>, ParameterError> {
+    let values = cf_parameters.to_vector();
+    let index = values
+        .iter()
+        .position(|(_, name)| *name == parameter)
+        .ok_or_else(|| throw_no_exist_error(parameter))?;
+    let (lower, upper) = cf_parameters.bounds()[index];
+    let value = values[index].0;
+    let bump = PARAMETER_BUMP * value.abs().max(1.0);
+    let get_prices = |bumped_value: f64| {
+        let mut bumped: Vec<f64> = values.iter().map(|(value, _)| *value).collect();
+        bumped[index] = bumped_value;
+        get_option_results_as_json(
+            option_kind,
+            sensitivity,
+            ImpliedVolatilityMode::Skip,
+            &cf_parameters.with_vector(&bumped),
+            pricing,
+            asset,
+            strikes,
+        )
+    };
+    let value_down = (value - bump).max(lower);
+    let value_up = (value + bump).min(upper);
+    let prices_down = get_prices(value_down)?;
+    let prices_up = get_prices(value_up)?;
+    Ok(prices_down
+        .into_iter()
+        .zip(prices_up)
+        .map(|(down, up)| GraphElement {
+            value: (up.value - down.value) / (value_up - value_down),
+            ..down
+        })
+        .collect())
+}
+
+/// Returns option prices along with their derivative with
+/// respect to every model parameter, by bumping each
+/// parameter in turn
+fn get_parameter_sensitivity_results(
+    option_kind: OptionKind,
+    cf_parameters: &CFParameters,
+    pricing: &PricingParameters,
+    asset: f64,
+    strikes: &[f64],
+) -> Result<Vec<GraphElement>, ParameterError> {
+    let prices = get_option_results_as_json(
+        option_kind,
+        Sensitivity::Price,
+        ImpliedVolatilityMode::Skip,
+        cf_parameters,
+        pricing,
+        asset,
+        strikes,
+    )?;
+    let sensitivities = cf_parameters
+        .to_vector()
+        .into_par_iter()
+        .map(|(_, parameter)| {
+            get_bumped_results(
+                option_kind,
+                Sensitivity::Price,
+                cf_parameters,
+                parameter,
+                pricing,
+                asset,
+                strikes,
+            )
+            .map(|results| (parameter, results))
+        })
+        .collect::<Result<Vec<_>, ParameterError>>()?;
+    Ok(prices
+        .into_iter()
+        .enumerate()
+        .map(|(index, element)| GraphElement {
+            parameter_sensitivities: Some(
+                sensitivities
+                    .iter()
+                    .map(|(parameter, results)| (parameter.to_string(), results[index].value))
+                    .collect(),
+            ),
+            ..element
+        })
+        .collect())
+}
+
+/// Returns the charm, the rate at which delta changes as
+/// time passes, from central differences of delta in the
+/// maturity.  `fang_oost_option` only differentiates prices
+/// in time, and rates and dividends are held fixed.
+fn get_charm_results(
+    option_kind: OptionKind,
+    cf_parameters: &CFParameters,
+    pricing: &PricingParameters,
+    asset: f64,
+    strikes: &[f64],
+) -> Result<Vec<GraphElement>, ParameterError> {
+    let bump = PARAMETER_BUMP * pricing.maturity;
+    let get_deltas = |maturity: f64| {
+        get_option_results_as_json(
+            option_kind,
+            Sensitivity::Delta,
+            ImpliedVolatilityMode::Skip,
+            cf_parameters,
+            &PricingParameters {
+                maturity,
+                ..*pricing
+            },
+            asset,
+            strikes,
+        )
+    };
+    let deltas_down = get_deltas(pricing.maturity - bump)?;
+    let deltas_up = get_deltas(pricing.maturity + bump)?;
+    Ok(deltas_down
+        .into_iter()
+        .zip(deltas_up)
+        .map(|(down, up)| GraphElement {
+            value: (down.value - up.value) / (2.0 * bump),
+            ..down
+        })
+        .collect())
+}
+
+/// Returns vega, vanna or volga from the first and second
+/// derivatives of the log characteristic function with
+/// respect to the volatility parameter.  Prices are linear
+/// in the characteristic function, and calls and puts only
+/// differ by terms that do not depend on the model
+/// parameters.
+fn get_cf_derivative_results<S, T>(
+    sensitivity: Sensitivity,
+    pricing: &PricingParameters,
+    asset: f64,
+    strikes: &[f64],
+    max_strike: f64,
+    inst_cf: S,
+    log_cf_derivatives: T,
+) -> Vec<GraphElement>
+where
+    S: Fn(&Complex<f64>) -> Complex<f64> + std::marker::Sync + std::marker::Send,
+    T: Fn(&Complex<f64>) -> (Complex<f64>, Complex<f64>) + std::marker::Sync + std::marker::Send,
+{
+    let PricingParameters {
+        num_u,
+        maturity,
+        rate,
+        ..
+    } = *pricing;
+    let cf_derivative = |u: &Complex<f64>| {
+        let (first, second) = log_cf_derivatives(u);
+        inst_cf(u)
+            * match sensitivity {
+                Sensitivity::Vanna => first * u, //multiplying by u differentiates in the log asset
+                Sensitivity::Volga => first * first + second,
+                _ => first,
+            }
+    };
+    let scale = match sensitivity {
+        Sensitivity::Vanna => asset,
+        _ => 1.0,
+    };
+    graph_no_iv_as_json(
+        option_pricing::fang_oost_put_price(
+            num_u,
+            asset,
+            strikes,
+            max_strike,
+            rate,
+            maturity,
+            &cf_derivative,
+        )
+        .map(
+            move |fang_oost::GraphElement { x, value }| fang_oost::GraphElement {
+                x,
+                value: value / scale,
+            },
+        ),
+    )
+}
+
+/// Returns vega, vanna or volga, which are the same for
+/// calls and puts.  Black-Scholes differentiates the
+/// characteristic function with respect to `sigma`, and
+/// Heston and Bates with respect to `v0`.  The remaining
+/// models difference the log characteristic function in
+/// `sigma`, the volatility of the diffusion component, so
+/// that volga is not a difference of differenced prices.
+fn get_volatility_results(
+    sensitivity: Sensitivity,
+    cf_parameters: &CFParameters,
+    pricing: &PricingParameters,
+    asset: f64,
+    strikes: &[f64],
+) -> Result<Vec<GraphElement>, ParameterError> {
+    let maturity = pricing.maturity;
+    let (results, volatility_parameter) = match cf_parameters {
+        CFParameters::BlackScholes(cf_params) => {
+            let (cf_inst, range) = get_model_cf(cf_parameters, pricing)?;
+            let max_strike = get_max_strike(asset, range);
+            let sigma = cf_params.sigma;
+            (
+                get_cf_derivative_results(
+                    sensitivity,
+                    pricing,
+                    asset,
+                    strikes,
+                    max_strike,
+                    &cf_inst,
+                    |u: &Complex<f64>| {
+                        let variance_term = maturity * (u * u - u);
+                        (sigma * variance_term, variance_term)
+                    },
+                ),
+                "sigma",
+            )
+        }
+        CFParameters::Heston(HestonParameters {
+            sigma,
+            speed,
+            eta_v,
+            rho,
+            ..
+        })
+        | CFParameters::Bates(BatesParameters {
+            sigma,
+            speed,
+            eta_v,
+            rho,
+            ..
+        }) => {
+            let (cf_inst, range) = get_model_cf(cf_parameters, pricing)?;
+            let max_strike = get_max_strike(asset, range);
+            (
+                get_cf_derivative_results(
+                    sensitivity,
+                    pricing,
+                    asset,
+                    strikes,
+                    max_strike,
+                    &cf_inst,
+                    |u: &Complex<f64>| {
+                        (
+                            cf_models::heston_log_cf_dv0(u, maturity, *sigma, *speed, *eta_v, *rho),
+                            Complex::new(0.0, 0.0), //affine in v0
+                        )
+                    },
+                ),
+                "v0",
+            )
+        }
+        _ => {
+            let values = cf_parameters.to_vector();
+            let index = values
+                .iter()
+                .position(|(_, name)| *name == "sigma")
+                .ok_or_else(|| throw_no_exist_error("sigma"))?;
+            let (lower, upper) = cf_parameters.bounds()[index];
+            let bump = PARAMETER_BUMP * values[index].0.abs().max(1.0);
+            //keep the stencil inside the bounds
+            let center = values[index].0.max(lower + bump).min(upper - bump);
+            let get_cf = |sigma: f64| {
+                let mut bumped: Vec<f64> = values.iter().map(|(value, _)| *value).collect();
+                bumped[index] = sigma;
+                get_model_cf(&cf_parameters.with_vector(&bumped), pricing)
+            };
+            let (cf_inst, range) = get_cf(center)?;
+            let (cf_up, _) = get_cf(center + bump)?;
+            let (cf_down, _) = get_cf(center - bump)?;
+            let max_strike = get_max_strike(asset, range);
+            (
+                get_cf_derivative_results(
+                    sensitivity,
+                    pricing,
+                    asset,
+                    strikes,
+                    max_strike,
+                    &cf_inst,
+                    |u: &Complex<f64>| {
+                        let cf = cf_inst(u);
+                        let log_up = (cf_up(u) / cf).ln();
+                        let log_down = (cf_down(u) / cf).ln();
+                        //the characteristic function underflows in the
+                        //tails, where it no longer contributes to prices
+                        if log_up.is_finite() && log_down.is_finite() {
+                            (
+                                (log_up - log_down) / (2.0 * bump),
+                                (log_up + log_down) / bump.powi(2),
+                            )
+                        } else {
+                            (Complex::new(0.0, 0.0), Complex::new(0.0, 0.0))
+                        }
+                    },
+                ),
+                "sigma",
+            )
+        }
+    };
+    Ok(results
+        .into_iter()
+        .map(|element| GraphElement {
+            volatility_parameter: Some(volatility_parameter.to_string()),
+            ..element
+        })
+        .collect())
+}
+
+/// Returns the zero rate at `time`, linearly interpolated
+/// between tenors and flat beyond the first and last ones.
+/// The curve must be non-empty with increasing tenors.
+/// # Examples
+///
+/// ```
+/// extern crate utils;
+/// use utils::constraints::ZeroRate;
+/// use utils::pricing_maps::get_zero_rate;
+/// # fn main() {
+/// let curve = vec![
+///     ZeroRate { tenor: 1.0, rate: 0.02 },
+///     ZeroRate { tenor: 3.0, rate: 0.04 },
+/// ];
+/// assert_eq!(get_zero_rate(&curve, 2.0), 0.03);
+/// # }
+/// ```
+pub fn get_zero_rate(curve: &[ZeroRate], time: f64) -> f64 {
+    match curve
+        .iter()
+        .position(|ZeroRate { tenor, .. }| *tenor >= time)
+    {
+        Some(0) => curve[0].rate,
+        Some(index) => {
+            let (left, right) = (&curve[index - 1], &curve[index]);
+            let weight = (time - left.tenor) / (right.tenor - left.tenor);
+            left.rate + weight * (right.rate - left.rate)
+        }
+        None => curve[curve.len() - 1].rate,
+    }
+}
+
+/// Returns the rate for `maturity` from the curve when one
+/// is provided, and the flat rate otherwise
+pub fn get_effective_rate(
+    rate: Option<f64>,
+    curve: Option<&[ZeroRate]>,
+    maturity: f64,
+) -> Option<f64> {
+    match curve {
+        Some(curve) => Some(get_zero_rate(curve, maturity)),
+        None => rate,
+    }
+}
+
+/// Resolves the rates and dividends at `maturity`.  Also
+/// returns the rate to report back, which is only provided
+/// when it is read from a curve.
+pub fn get_pricing_parameters(
+    rates: &RateParameters,
+    asset: Option<f64>,
+    maturity: f64,
+    num_u: usize,
+    scale: f64,
+) -> Result<(PricingParameters, Option<f64>), ParameterError> {
+    let RateParameters {
+        rate,
+        rate_curve,
+        dividend_yield,
+        dividend_curve,
+        borrow_rate,
+        dividends,
+    } = rates;
+    let rate = get_effective_rate(*rate, rate_curve.as_deref(), maturity)
+        .ok_or_else(|| throw_no_exist_error("rate"))?;
+    let dividend_yield = get_effective_rate(*dividend_yield, dividend_curve.as_deref(), maturity)
+        .unwrap_or(0.0)
+        + borrow_rate.unwrap_or(0.0);
+    let cash_dividend_shift = get_cash_dividend_shift(
+        asset,
+        dividends.as_deref().unwrap_or_default(),
+        rate_curve.as_deref(),
+        rate,
+        maturity,
+    )?;
+    Ok((
+        PricingParameters {
+            num_u,
+            scale,
+            maturity,
+            rate,
+            dividend_yield,
+            cash_dividend_shift,
+        },
+        rate_curve.as_ref().map(|_| rate),
+    ))
+}
+
+/// Returns the log of the escrowed asset relative to the
+/// asset, where the escrowed asset subtracts the present
+/// value of the cash dividends paid before maturity.  The
+/// asset is only required when there are such dividends.
+/// Dividends are discounted on the curve when provided.
+pub fn get_cash_dividend_shift(
+    asset: Option<f64>,
+    dividends: &[CashDividend],
+    rate_curve: Option<&[ZeroRate]>,
+    rate: f64,
+    maturity: f64,
+) -> Result<f64, ParameterError> {
+    let present_value: f64 = dividends
+        .iter()
+        .filter(|CashDividend { time, .. }| *time <= maturity)
+        .map(|CashDividend { time, amount }| {
+            let discount_rate = rate_curve.map_or(rate, |curve| get_zero_rate(curve, *time));
+            amount * (-discount_rate * time).exp()
+        })
+        .sum();
+    if present_value == 0.0 {
+        return Ok(0.0);
+    }
+    let asset = asset.ok_or_else(|| throw_no_exist_error("asset"))?;
+    if present_value >= asset {
+        Err(ParameterError::new(&ErrorType::OutOfBounds(
+            "dividends".to_string(),
+        )))
+    } else {
+        Ok((1.0 - present_value / asset).ln())
+    }
+}
+
+const MAX_SIMS: usize = 100;
+const PRECISION: f64 = 0.0000001;
+
+fn get_risk_measure_results(
+    num_u: usize,
+    (x_min, x_max): TruncationRange,
+    quantile: f64,
     inst_cf: &(impl Fn(&Complex<f64>) -> Complex<f64> + std::marker::Sync),
 ) -> Result<cf_dist_utils::RiskMetric, cf_dist_utils::ValueAtRiskError> {
     cf_dist_utils::get_expected_shortfall_and_value_at_risk(
-        quantile,
-        num_u,
-        -x_max_density,
-        x_max_density,
-        MAX_SIMS,
-        PRECISION,
-        &inst_cf,
+        quantile, num_u, x_min, x_max, MAX_SIMS, PRECISION, inst_cf,
     )
 }
 
@@ -421,9 +2213,12 @@ mod tests {
     use approx::*;
     use rand::{distributions::Distribution, distributions::Uniform, rngs::StdRng, SeedableRng};
     #[test]
-    fn get_fn_indicators_gets_match() {
-        let model = get_fn_indicators("put", "price").unwrap();
-        assert_eq!(model, PUT_PRICE);
+    fn option_kind_and_sensitivity_from_param() {
+        assert_eq!(OptionKind::from_param("put").unwrap(), OptionKind::Put);
+        assert_eq!(
+            Sensitivity::from_param("gamma").unwrap(),
+            Sensitivity::Gamma
+        );
     }
     fn get_rng_seed(seed: u64) -> StdRng {
         SeedableRng::seed_from_u64(seed)
@@ -522,11 +2317,6 @@ mod tests {
         let sigma = 0.2072;
         let speed = 0.87;
         let v0 = 1.2104;
-        let max_strike = get_max_strike(
-            asset,
-            10.0,
-            cf_functions::merton::jump_diffusion_vol(sigma, lambda, mu_l, sig_l, maturity),
-        );
 
         let strikes = vec![
             85.0, 90.0, 100.0, 110.0, 120.0, 125.0, 130.0, 135.0, 140.0, 145.0, 150.0, 155.0,
@@ -537,21 +2327,40 @@ mod tests {
         let inst_cf = cf_functions::merton::merton_time_change_cf(
             maturity, rate, lambda, mu_l, sig_l, sigma, v0, speed, eta_v, rho,
         );
+        let parameters = MertonParameters {
+            lambda,
+            mu_l,
+            sig_l,
+            sigma,
+            v0,
+            speed,
+            eta_v,
+            rho,
+        };
+        let (_, cumulants) = get_merton_cf(&parameters, maturity, rate, 0.0).unwrap();
+        let max_strike = get_max_strike(asset, cumulants.range(10.0));
         let num_u = 256;
         let prices = option_pricing::fang_oost_call_price(
             num_u, asset, &strikes, max_strike, rate, maturity, &inst_cf,
         );
-        let result = call_iv_as_json(prices, asset, rate, maturity);
+        let result = call_iv_as_json(prices, true, asset, rate, maturity);
         assert!(result.is_ok());
     }
     #[test]
-    fn get_fn_indicators_no_match() {
+    fn option_kind_no_match() {
         assert_eq!(
-            get_fn_indicators(&"something".to_string(), &"somethingelse".to_string())
-                .unwrap_err()
-                .to_string(),
-            "Function indicator something_somethingelse does not exist."
+            OptionKind::from_param("something").unwrap_err().to_string(),
+            "Function indicator something does not exist."
+        );
+    }
+    #[test]
+    fn sensitivity_no_match() {
+        //density is its own route, not a sensitivity
+        assert_eq!(
+            Sensitivity::from_param("").unwrap_err().to_string(),
+            "Function indicator  does not exist."
         );
+        assert!(Sensitivity::from_param("density").is_err());
     }
     #[test]
     fn test_cgmy_price_1() {
@@ -575,14 +2384,19 @@ mod tests {
         let rate = 0.1;
         let asset = 100.0;
         let results = get_option_results_as_json(
-            CALL_PRICE,
-            true,
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Lenient,
             &CFParameters::CGMY(parameters),
-            10.0,
-            num_u,
+            &PricingParameters {
+                num_u,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -609,14 +2423,19 @@ mod tests {
         let rate = 0.1;
         let asset = 100.0;
         let results = get_option_results_as_json(
-            CALL_PRICE,
-            false,
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
             &CFParameters::CGMY(parameters),
-            10.0,
-            num_u,
+            &PricingParameters {
+                num_u,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -643,14 +2462,19 @@ mod tests {
         let rate = 0.1;
         let asset = 100.0;
         let results = get_option_results_as_json(
-            CALL_PRICE,
-            false,
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
             &CFParameters::CGMY(parameters),
-            10.0,
-            num_u,
+            &PricingParameters {
+                num_u,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -677,14 +2501,19 @@ mod tests {
         let rate = 0.1;
         let asset = 38.0;
         let results = get_option_results_as_json(
-            CALL_PRICE,
-            false,
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
             &CFParameters::Merton(parameters),
-            10.0,
-            num_u,
+            &PricingParameters {
+                num_u,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -716,14 +2545,19 @@ mod tests {
         let rate = 0.0;
         let asset = 100.0;
         let results = get_option_results_as_json(
-            CALL_PRICE,
-            false,
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
             &CFParameters::Merton(parameters),
-            10.0,
-            num_u,
+            &PricingParameters {
+                num_u,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
@@ -750,116 +2584,1811 @@ mod tests {
         let rate = 0.0;
         let asset = 100.0;
         let results = get_option_results_as_json(
-            CALL_PRICE,
-            false,
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
             &CFParameters::Heston(parameters),
-            10.0,
-            num_u,
+            &PricingParameters {
+                num_u,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
         assert_abs_diff_eq!(results[0].value, 5.78515545, epsilon = 0.0001);
     }
     #[test]
-    fn test_monte_carlo() {
-        // ../../techdoc/OptionCalculation.Rnw
-        let parameters = MertonParameters {
-            sigma: 0.2,
-            lambda: 0.5,
-            mu_l: -0.05,
-            sig_l: 0.1,
-            speed: 0.3,
-            v0: 0.9,
-            eta_v: 0.2,
-            rho: -0.5,
+    fn test_kou_price() {
+        //reference prices from a Poisson series over the number of up and down
+        //jumps, averaging Black-Scholes over the jump sizes with mpmath
+        let parameters = KouParameters {
+            sigma: 0.16,
+            lambda: 1.0,
+            p: 0.4,
+            eta_up: 10.0,
+            eta_down: 5.0,
+            speed: 0.0,
+            v0: 1.0,
+            eta_v: 0.0,
+            rho: 0.0,
         };
-        let strikes = vec![50.0];
+        let strikes = vec![90.0, 100.0, 110.0];
         let num_u: usize = 256;
-        let t = 1.0;
-        let rate = 0.03;
-        let asset = 50.0;
+        let t = 0.5;
+        let rate = 0.05;
+        let asset = 100.0;
         let results = get_option_results_as_json(
-            CALL_PRICE,
-            false,
-            &CFParameters::Merton(parameters),
-            10.0,
-            num_u,
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
+            &CFParameters::Kou(parameters),
+            &PricingParameters {
+                num_u,
+                scale: 20.0, //exponential tails need a wider domain than the default
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
         )
         .unwrap();
-        //MC price is 4.793274
-        assert!(results[0].value > 4.781525);
-        assert!(results[0].value < 4.805023);
+        assert_abs_diff_eq!(results[0].value, 14.8118905452, epsilon = 0.0001);
+        assert_abs_diff_eq!(results[1].value, 7.95942920298, epsilon = 0.0001);
+        assert_abs_diff_eq!(results[2].value, 3.59964981455, epsilon = 0.0001);
     }
     #[test]
-    fn test_risk_measures() {
-        //https://github.com/phillyfan1138/levy-functions/issues/27
-        let parameters = MertonParameters {
-            sigma: 0.3183,
-            lambda: 0.204516,
-            mu_l: -0.302967,
-            sig_l: 0.220094,
-            speed: 2.6726,
-            v0: 0.237187,
+    fn test_kou_price_subset_black_scholes() {
+        let parameters = KouParameters {
+            sigma: 0.3,
+            lambda: 0.0,
+            p: 0.4,
+            eta_up: 10.0,
+            eta_down: 5.0,
+            speed: 0.0,
+            v0: 1.0,
             eta_v: 0.0,
-            rho: -0.182754,
+            rho: 0.0,
         };
+        let strikes = vec![100.0];
         let num_u: usize = 256;
-        let t = 0.187689;
-        let rate = 0.004;
-        let quantile = 0.01;
-        let results = get_risk_measure_results_as_json(
-            &CFParameters::Merton(parameters),
-            5.0,
-            num_u,
-            t,
-            rate,
-            quantile,
+        let t = 1.0;
+        let rate = 0.03;
+        let asset = 100.0;
+        let results = get_option_results_as_json(
+            OptionKind::Put,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
+            &CFParameters::Kou(parameters),
+            &PricingParameters {
+                num_u,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            asset,
+            &strikes,
         )
         .unwrap();
-        assert_abs_diff_eq!(results.value_at_risk, 0.261503, epsilon = 0.00001);
+        assert_abs_diff_eq!(
+            results[0].value,
+            black_scholes::put(asset, 100.0, rate, 0.3, t),
+            epsilon = 0.0001
+        );
     }
     #[test]
-    fn test_error_for_out_of_bounds_constant() {
-        let sig_l = 0.05_f64.sqrt();
-        let mu_l = -sig_l.powi(2) * 0.5;
-        let parameters = MertonParameters {
-            sigma: sig_l,
-            lambda: 1.0,
-            mu_l,
-            sig_l,
+    fn test_kou_density_and_risk_measures() {
+        let parameters = KouParameters {
+            sigma: 0.2,
+            lambda: 0.8,
+            p: 0.3,
+            eta_up: 10.0,
+            eta_down: 4.0,
+            speed: 0.5,
+            v0: 0.9,
+            eta_v: 0.3,
+            rho: -0.5,
+        };
+        let cf_parameters = CFParameters::Kou(parameters);
+        let density = get_density_results_as_json(
+            &cf_parameters,
+            &PricingParameters {
+                num_u: 256,
+                scale: 5.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+        )
+        .unwrap();
+        assert!(density.iter().all(|element| element.value.is_finite()));
+        let risk_metric = get_risk_measure_results_as_json(
+            &cf_parameters,
+            &PricingParameters {
+                num_u: 256,
+                scale: 5.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            0.01,
+        )
+        .unwrap();
+        assert!(risk_metric.expected_shortfall > risk_metric.value_at_risk);
+    }
+    #[test]
+    fn test_vg_price() {
+        //Madan, Carr and Chang (1998), Black-Scholes conditional on the
+        //gamma time integrated over its density with mpmath
+        let parameters = VGParameters {
+            sigma_vg: 0.12,
+            nu: 0.2,
+            theta: -0.14,
+            sigma: 0.0,
             speed: 0.0,
             v0: 1.0,
             eta_v: 0.0,
             rho: 0.0,
         };
-        let strikes = vec![35.0];
+        let strikes = vec![90.0, 100.0, 110.0];
         let num_u: usize = 256;
-        let t = 0.5;
+        let t = 1.0;
         let rate = 0.1;
-        let asset = 38.0;
-        let integer_that_is_not_an_option = -1;
+        let asset = 100.0;
         let results = get_option_results_as_json(
-            integer_that_is_not_an_option,
-            false,
-            &CFParameters::Merton(parameters),
-            10.0,
-            num_u,
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
+            &CFParameters::VG(parameters),
+            &PricingParameters {
+                num_u,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            asset,
+            &strikes,
+        )
+        .unwrap();
+        assert_abs_diff_eq!(results[0].value, 19.0993547242, epsilon = 0.0001);
+        assert_abs_diff_eq!(results[1].value, 11.3700278104, epsilon = 0.0001);
+        assert_abs_diff_eq!(results[2].value, 5.42959554304, epsilon = 0.0001);
+    }
+    #[test]
+    fn test_nig_price() {
+        //reference prices from Black-Scholes conditional on the inverse
+        //Gaussian time, integrated over its density with mpmath
+        let parameters = NIGParameters {
+            alpha: 15.0,
+            beta: -5.0,
+            delta: 0.5,
+            sigma: 0.0,
+            speed: 0.0,
+            v0: 1.0,
+            eta_v: 0.0,
+            rho: 0.0,
+        };
+        let strikes = vec![90.0, 100.0, 110.0];
+        let num_u: usize = 256;
+        let t = 0.5;
+        let rate = 0.05;
+        let asset = 100.0;
+        let results = get_option_results_as_json(
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
+            &CFParameters::NIG(parameters),
+            &PricingParameters {
+                num_u,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
             asset,
-            t,
-            rate,
             &strikes,
+        )
+        .unwrap();
+        assert_abs_diff_eq!(results[0].value, 13.6140017912, epsilon = 0.0001);
+        assert_abs_diff_eq!(results[1].value, 6.67725755646, epsilon = 0.0001);
+        assert_abs_diff_eq!(results[2].value, 2.49405791846, epsilon = 0.0001);
+    }
+    #[test]
+    fn test_vg_and_nig_density_and_risk_measures() {
+        let vg = CFParameters::VG(VGParameters {
+            sigma_vg: 0.12,
+            nu: 0.2,
+            theta: -0.14,
+            sigma: 0.1,
+            speed: 0.5,
+            v0: 0.9,
+            eta_v: 0.3,
+            rho: -0.5,
+        });
+        let nig = CFParameters::NIG(NIGParameters {
+            alpha: 15.0,
+            beta: -5.0,
+            delta: 0.5,
+            sigma: 0.1,
+            speed: 0.5,
+            v0: 0.9,
+            eta_v: 0.3,
+            rho: -0.5,
+        });
+        for cf_parameters in [vg, nig].iter() {
+            let density = get_density_results_as_json(
+                cf_parameters,
+                &PricingParameters {
+                    num_u: 256,
+                    scale: 5.0,
+                    maturity: 1.0,
+                    rate: 0.03,
+                    dividend_yield: 0.0,
+                    cash_dividend_shift: 0.0,
+                },
+            )
+            .unwrap();
+            assert!(density.iter().all(|element| element.value.is_finite()));
+            let risk_metric = get_risk_measure_results_as_json(
+                cf_parameters,
+                &PricingParameters {
+                    num_u: 256,
+                    scale: 5.0,
+                    maturity: 1.0,
+                    rate: 0.03,
+                    dividend_yield: 0.0,
+                    cash_dividend_shift: 0.0,
+                },
+                0.01,
+            )
+            .unwrap();
+            assert!(risk_metric.expected_shortfall > risk_metric.value_at_risk);
+        }
+    }
+    #[test]
+    fn test_bates_price() {
+        //reference prices from Gil-Pelaez inversion of the Bates characteristic function
+        let parameters = BatesParameters {
+            lambda: 0.5,
+            mu_l: -0.1,
+            sig_l: 0.1,
+            sigma: 0.2,
+            v0: 0.04,
+            speed: 1.5,
+            eta_v: 0.5,
+            rho: -0.5,
+        };
+        let strikes = vec![90.0, 100.0, 110.0];
+        let num_u: usize = 256;
+        let t = 0.5;
+        let rate = 0.03;
+        let asset = 100.0;
+        let results = get_option_results_as_json(
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
+            &CFParameters::Bates(parameters),
+            &PricingParameters {
+                num_u,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            asset,
+            &strikes,
+        )
+        .unwrap();
+        assert_abs_diff_eq!(results[0].value, 13.5514309345, epsilon = 0.0001);
+        assert_abs_diff_eq!(results[1].value, 6.68527868747, epsilon = 0.0001);
+        assert_abs_diff_eq!(results[2].value, 2.32681649272, epsilon = 0.0001);
+    }
+    #[test]
+    fn test_bates_price_subset_heston() {
+        //https://mpra.ub.uni-muenchen.de/8914/4/MPRA_paper_8914.pdf pg 15
+        let parameters = BatesParameters {
+            lambda: 0.0,
+            mu_l: -0.1,
+            sig_l: 0.1,
+            sigma: 0.0398_f64.sqrt(),
+            v0: 0.0175,
+            speed: 1.5768,
+            eta_v: 0.5751,
+            rho: -0.5711,
+        };
+        let strikes = vec![100.0];
+        let num_u: usize = 256;
+        let t = 1.0;
+        let rate = 0.0;
+        let asset = 100.0;
+        let results = get_option_results_as_json(
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
+            &CFParameters::Bates(parameters),
+            &PricingParameters {
+                num_u,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            asset,
+            &strikes,
+        )
+        .unwrap();
+        assert_abs_diff_eq!(results[0].value, 5.78515545, epsilon = 0.0001);
+    }
+    #[test]
+    fn test_bates_density_and_risk_measures() {
+        let parameters = BatesParameters {
+            lambda: 0.5,
+            mu_l: -0.1,
+            sig_l: 0.1,
+            sigma: 0.2,
+            v0: 0.04,
+            speed: 1.5,
+            eta_v: 0.5,
+            rho: -0.5,
+        };
+        let cf_parameters = CFParameters::Bates(parameters);
+        let density = get_density_results_as_json(
+            &cf_parameters,
+            &PricingParameters {
+                num_u: 256,
+                scale: 5.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+        )
+        .unwrap();
+        assert!(density.iter().all(|element| element.value.is_finite()));
+        let risk_metric = get_risk_measure_results_as_json(
+            &cf_parameters,
+            &PricingParameters {
+                num_u: 256,
+                scale: 5.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            0.01,
+        )
+        .unwrap();
+        assert!(risk_metric.expected_shortfall > risk_metric.value_at_risk);
+    }
+    #[test]
+    fn test_black_scholes_matches_closed_form() {
+        let strikes = vec![80.0, 100.0, 120.0];
+        let num_u: usize = 256;
+        let t = 0.5; //away from one, where the maturity drops out of theta
+        let rate = 0.03;
+        let asset = 100.0;
+        let sensitivities = [
+            Sensitivity::Price,
+            Sensitivity::Delta,
+            Sensitivity::Gamma,
+            Sensitivity::Theta,
+            Sensitivity::Vega,
+            Sensitivity::Rho,
+            Sensitivity::Vanna,
+            Sensitivity::Volga,
+            Sensitivity::Charm,
+            Sensitivity::Speed,
+        ];
+        for option_kind in [OptionKind::Call, OptionKind::Put].iter() {
+            for sensitivity in sensitivities.iter() {
+                let results = get_option_results_as_json(
+                    *option_kind,
+                    *sensitivity,
+                    ImpliedVolatilityMode::Skip,
+                    &CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+                    &PricingParameters {
+                        num_u,
+                        scale: 10.0,
+                        maturity: t,
+                        rate,
+                        dividend_yield: 0.0,
+                        cash_dividend_shift: 0.0,
+                    },
+                    asset,
+                    &strikes,
+                )
+                .unwrap();
+                for (result, strike) in results.iter().zip(strikes.iter()) {
+                    assert_eq!(result.at_point, *strike);
+                    assert_abs_diff_eq!(
+                        result.value,
+                        result.closed_form.unwrap(),
+                        epsilon = 0.0001
+                    );
+                }
+            }
+        }
+    }
+    #[test]
+    fn test_black_scholes_closed_form_price() {
+        let results = get_option_results_as_json(
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Lenient,
+            &CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+            &PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            100.0,
+            &[100.0],
+        )
+        .unwrap();
+        assert_eq!(
+            results[0].closed_form.unwrap(),
+            black_scholes::call(100.0, 100.0, 0.03, 0.3, 1.0)
+        );
+        assert_abs_diff_eq!(results[0].iv.unwrap(), 0.3, epsilon = 0.0001);
+    }
+    #[test]
+    fn test_black_scholes_with_dividends_matches_closed_form() {
+        let strikes = vec![80.0, 100.0, 120.0];
+        let dividend_yield = 0.04;
+        let sensitivities = [
+            Sensitivity::Price,
+            Sensitivity::Delta,
+            Sensitivity::Gamma,
+            Sensitivity::Theta,
+            Sensitivity::Vega,
+            Sensitivity::Rho,
+            Sensitivity::Vanna,
+            Sensitivity::Volga,
+            Sensitivity::Charm,
+            Sensitivity::Speed,
+        ];
+        for option_kind in [OptionKind::Call, OptionKind::Put].iter() {
+            for sensitivity in sensitivities.iter() {
+                let results = get_option_results_as_json(
+                    *option_kind,
+                    *sensitivity,
+                    ImpliedVolatilityMode::Skip,
+                    &CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+                    &PricingParameters {
+                        num_u: 256,
+                        scale: 10.0,
+                        maturity: 0.5,
+                        rate: 0.03,
+                        dividend_yield,
+                        cash_dividend_shift: 0.0,
+                    },
+                    100.0,
+                    &strikes,
+                )
+                .unwrap();
+                for result in results.iter() {
+                    assert_abs_diff_eq!(
+                        result.value,
+                        result.closed_form.unwrap(),
+                        epsilon = 0.0001
+                    );
+                }
+            }
+        }
+        let put_price = get_black_scholes_closed_form(
+            OptionKind::Put,
+            Sensitivity::Price,
+            100.0,
+            100.0,
+            &PricingParameters {
+                num_u: 0,
+                scale: 0.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield,
+                cash_dividend_shift: 0.0,
+            },
+            0.3,
+        )
+        .unwrap();
+        //reference from the generalized Black-Scholes formula
+        assert_abs_diff_eq!(put_price, 12.0027299180, epsilon = 0.0000001);
+    }
+    #[test]
+    fn test_kou_volga_matches_second_difference_of_prices() {
+        let get_parameters = |sigma: f64| {
+            CFParameters::Kou(KouParameters {
+                sigma,
+                lambda: 1.0,
+                p: 0.4,
+                eta_up: 10.0,
+                eta_down: 5.0,
+                speed: 0.5,
+                v0: 0.9,
+                eta_v: 0.3,
+                rho: -0.5,
+            })
+        };
+        let pricing = PricingParameters {
+            num_u: 256,
+            scale: 10.0,
+            maturity: 0.5,
+            rate: 0.05,
+            dividend_yield: 0.0,
+            cash_dividend_shift: 0.0,
+        };
+        let strikes = vec![90.0, 100.0, 110.0];
+        let get_results = |sensitivity: Sensitivity, sigma: f64| {
+            get_option_results_as_json(
+                OptionKind::Call,
+                sensitivity,
+                ImpliedVolatilityMode::Skip,
+                &get_parameters(sigma),
+                &pricing,
+                100.0,
+                &strikes,
+            )
+            .unwrap()
+        };
+        let (sigma, bump) = (0.2, 0.01);
+        let volga = get_results(Sensitivity::Volga, sigma);
+        let prices_down = get_results(Sensitivity::Price, sigma - bump);
+        let prices = get_results(Sensitivity::Price, sigma);
+        let prices_up = get_results(Sensitivity::Price, sigma + bump);
+        for (index, result) in volga.iter().enumerate() {
+            let second_difference = (prices_up[index].value - 2.0 * prices[index].value
+                + prices_down[index].value)
+                / bump.powi(2);
+            assert_abs_diff_eq!(result.value, second_difference, epsilon = 0.05);
+            assert_eq!(result.volatility_parameter, Some("sigma".to_string()));
+        }
+    }
+    #[test]
+    fn test_volatility_parameter_label() {
+        let pricing = PricingParameters {
+            num_u: 128,
+            scale: 10.0,
+            maturity: 1.0,
+            rate: 0.03,
+            dividend_yield: 0.0,
+            cash_dividend_shift: 0.0,
+        };
+        let heston = CFParameters::Heston(HestonParameters {
+            sigma: 0.2,
+            v0: 0.04,
+            speed: 1.5,
+            eta_v: 0.5,
+            rho: -0.5,
+        });
+        let get_results = |sensitivity: Sensitivity| {
+            get_option_results_as_json(
+                OptionKind::Put,
+                sensitivity,
+                ImpliedVolatilityMode::Skip,
+                &heston,
+                &pricing,
+                100.0,
+                &[100.0],
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            get_results(Sensitivity::Vega)[0].volatility_parameter,
+            Some("v0".to_string())
+        );
+        assert_eq!(
+            get_results(Sensitivity::Price)[0].volatility_parameter,
+            None
+        );
+    }
+    #[test]
+    fn test_implied_volatility_rejected_for_volatility_greeks() {
+        let cf_parameters = CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 });
+        for sensitivity in [
+            Sensitivity::Vega,
+            Sensitivity::Vanna,
+            Sensitivity::Volga,
+            Sensitivity::Charm,
+            Sensitivity::ParameterSensitivities,
+        ]
+        .iter()
+        {
+            let result = get_option_results_as_json(
+                OptionKind::Call,
+                *sensitivity,
+                ImpliedVolatilityMode::Lenient,
+                &cf_parameters,
+                &PricingParameters {
+                    num_u: 64,
+                    scale: 10.0,
+                    maturity: 1.0,
+                    rate: 0.03,
+                    dividend_yield: 0.0,
+                    cash_dividend_shift: 0.0,
+                },
+                100.0,
+                &[100.0],
+            );
+            assert_eq!(
+                result.err().unwrap(),
+                ParameterError::new(&ErrorType::UnsupportedOption(
+                    "include_implied_volatility".to_string(),
+                    format!("{:?}", sensitivity)
+                ))
+            );
+        }
+    }
+    #[test]
+    fn test_merton_volatility_greeks_without_jumps_are_black_scholes() {
+        let cf_parameters = CFParameters::Merton(MertonParameters {
+            lambda: 0.0,
+            mu_l: -0.05,
+            sig_l: 0.1,
+            sigma: 0.3,
+            v0: 1.0,
+            speed: 0.5,
+            eta_v: 0.0,
+            rho: 0.0,
+        });
+        let strikes = vec![80.0, 100.0, 120.0];
+        let sensitivities = [Sensitivity::Vega, Sensitivity::Vanna, Sensitivity::Volga];
+        for option_kind in [OptionKind::Call, OptionKind::Put].iter() {
+            for sensitivity in sensitivities.iter() {
+                let results = get_option_results_as_json(
+                    *option_kind,
+                    *sensitivity,
+                    ImpliedVolatilityMode::Skip,
+                    &cf_parameters,
+                    &PricingParameters {
+                        num_u: 256,
+                        scale: 10.0,
+                        maturity: 1.0,
+                        rate: 0.03,
+                        dividend_yield: 0.0,
+                        cash_dividend_shift: 0.0,
+                    },
+                    100.0,
+                    &strikes,
+                )
+                .unwrap();
+                for result in results.iter() {
+                    assert_abs_diff_eq!(
+                        result.value,
+                        get_black_scholes_closed_form(
+                            *option_kind,
+                            *sensitivity,
+                            100.0,
+                            result.at_point,
+                            &PricingParameters {
+                                num_u: 0,
+                                scale: 0.0,
+                                maturity: 1.0,
+                                rate: 0.03,
+                                dividend_yield: 0.0,
+                                cash_dividend_shift: 0.0
+                            },
+                            0.3
+                        )
+                        .unwrap(),
+                        epsilon = 0.001
+                    );
+                }
+            }
+        }
+    }
+    #[test]
+    fn test_heston_volatility_greeks_match_bump() {
+        let cf_parameters = CFParameters::Heston(HestonParameters {
+            sigma: 0.2,
+            v0: 0.04,
+            speed: 1.5,
+            eta_v: 0.5,
+            rho: -0.5,
+        });
+        let strikes = vec![80.0, 100.0, 120.0];
+        let sensitivities = [
+            (Sensitivity::Vega, Sensitivity::Price),
+            (Sensitivity::Vanna, Sensitivity::Delta),
+            (Sensitivity::Volga, Sensitivity::Vega),
+        ];
+        for (sensitivity, bumped_sensitivity) in sensitivities.iter() {
+            let analytic = get_option_results_as_json(
+                OptionKind::Put,
+                *sensitivity,
+                ImpliedVolatilityMode::Skip,
+                &cf_parameters,
+                &PricingParameters {
+                    num_u: 256,
+                    scale: 10.0,
+                    maturity: 0.5,
+                    rate: 0.03,
+                    dividend_yield: 0.01,
+                    cash_dividend_shift: 0.0,
+                },
+                100.0,
+                &strikes,
+            )
+            .unwrap();
+            let bumped = get_bumped_results(
+                OptionKind::Call,
+                *bumped_sensitivity,
+                &cf_parameters,
+                "v0",
+                &PricingParameters {
+                    num_u: 256,
+                    scale: 10.0,
+                    maturity: 0.5,
+                    rate: 0.03,
+                    dividend_yield: 0.01,
+                    cash_dividend_shift: 0.0,
+                },
+                100.0,
+                &strikes,
+            )
+            .unwrap();
+            for (analytic, bumped) in analytic.iter().zip(bumped.iter()) {
+                assert_abs_diff_eq!(analytic.value, bumped.value, epsilon = 0.001);
+            }
+        }
+    }
+    #[test]
+    fn test_bates_rho_matches_bump() {
+        let get_results = |sensitivity: Sensitivity, rate: f64| {
+            get_option_results_as_json(
+                OptionKind::Call,
+                sensitivity,
+                ImpliedVolatilityMode::Skip,
+                &CFParameters::Bates(BatesParameters {
+                    lambda: 0.5,
+                    mu_l: -0.1,
+                    sig_l: 0.1,
+                    sigma: 0.2,
+                    v0: 0.04,
+                    speed: 1.5,
+                    eta_v: 0.5,
+                    rho: -0.5,
+                }),
+                &PricingParameters {
+                    num_u: 256,
+                    scale: 10.0,
+                    maturity: 0.5,
+                    rate,
+                    dividend_yield: 0.01,
+                    cash_dividend_shift: 0.0,
+                },
+                100.0,
+                &[90.0, 100.0, 110.0],
+            )
+            .unwrap()
+        };
+        let bump = 0.0001;
+        let rho = get_results(Sensitivity::Rho, 0.03);
+        let up = get_results(Sensitivity::Price, 0.03 + bump);
+        let down = get_results(Sensitivity::Price, 0.03 - bump);
+        for ((rho, up), down) in rho.iter().zip(up.iter()).zip(down.iter()) {
+            assert_abs_diff_eq!(
+                rho.value,
+                (up.value - down.value) / (2.0 * bump),
+                epsilon = 0.001
+            );
+        }
+    }
+    #[test]
+    fn test_parameter_sensitivities() {
+        let strikes = vec![90.0, 110.0];
+        let results = get_option_results_as_json(
+            OptionKind::Put,
+            Sensitivity::ParameterSensitivities,
+            ImpliedVolatilityMode::Skip,
+            &CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+            &PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            100.0,
+            &strikes,
+        )
+        .unwrap();
+        for result in results.iter() {
+            assert_abs_diff_eq!(
+                result.value,
+                black_scholes::put(100.0, result.at_point, 0.03, 0.3, 1.0),
+                epsilon = 0.0001
+            );
+            assert!(result.closed_form.is_none());
+            assert_abs_diff_eq!(
+                result.parameter_sensitivities.as_ref().unwrap()["sigma"],
+                black_scholes::put_vega(100.0, result.at_point, 0.03, 0.3, 1.0),
+                epsilon = 0.001
+            );
+        }
+        let cf_parameters = CFParameters::Merton(MertonParameters {
+            lambda: 0.5,
+            mu_l: -0.05,
+            sig_l: 0.1,
+            sigma: 0.2,
+            v0: 0.9,
+            speed: 0.5,
+            eta_v: 0.3,
+            rho: -0.5,
+        });
+        let results = get_option_results_as_json(
+            OptionKind::Call,
+            Sensitivity::ParameterSensitivities,
+            ImpliedVolatilityMode::Skip,
+            &cf_parameters,
+            &PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            100.0,
+            &strikes,
+        )
+        .unwrap();
+        let names: Vec<&str> = cf_parameters
+            .to_vector()
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect();
+        for result in results.iter() {
+            let sensitivities = result.parameter_sensitivities.as_ref().unwrap();
+            assert_eq!(sensitivities.len(), names.len());
+            for name in names.iter() {
+                assert!(sensitivities[*name].is_finite());
+            }
+            //jumps add variance, so calls gain from more of them
+            assert!(sensitivities["lambda"] > 0.0);
+        }
+    }
+    #[test]
+    fn test_greeks_match_single_sensitivities() {
+        let cf_parameters = CFParameters::Heston(HestonParameters {
+            sigma: 0.2,
+            v0: 0.04,
+            speed: 1.5,
+            eta_v: 0.5,
+            rho: -0.5,
+        });
+        let strikes = vec![80.0, 100.0, 120.0];
+        let greeks = get_greeks_results_as_json(
+            OptionKind::Call,
+            &GreeksSelection::new(None, ImpliedVolatilityMode::Lenient).unwrap(),
+            &cf_parameters,
+            &PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: 0.5,
+                rate: 0.03,
+                dividend_yield: 0.01,
+                cash_dividend_shift: 0.0,
+            },
+            100.0,
+            &strikes,
+        )
+        .unwrap();
+        let get_results = |sensitivity: Sensitivity| {
+            get_option_results_as_json(
+                OptionKind::Call,
+                sensitivity,
+                ImpliedVolatilityMode::Lenient,
+                &cf_parameters,
+                &PricingParameters {
+                    num_u: 256,
+                    scale: 10.0,
+                    maturity: 0.5,
+                    rate: 0.03,
+                    dividend_yield: 0.01,
+                    cash_dividend_shift: 0.0,
+                },
+                100.0,
+                &strikes,
+            )
+            .unwrap()
+        };
+        let prices = get_results(Sensitivity::Price);
+        let deltas = get_results(Sensitivity::Delta);
+        let gammas = get_results(Sensitivity::Gamma);
+        let thetas = get_results(Sensitivity::Theta);
+        for (index, element) in greeks.iter().enumerate() {
+            assert_eq!(element.at_point, strikes[index]);
+            assert_abs_diff_eq!(
+                element.price.unwrap(),
+                prices[index].value,
+                epsilon = 0.0000001
+            );
+            assert_abs_diff_eq!(
+                element.delta.unwrap(),
+                deltas[index].value,
+                epsilon = 0.0000001
+            );
+            assert_abs_diff_eq!(
+                element.gamma.unwrap(),
+                gammas[index].value,
+                epsilon = 0.0000001
+            );
+            assert_abs_diff_eq!(
+                element.theta.unwrap(),
+                thetas[index].value,
+                epsilon = 0.0000001
+            );
+            assert_abs_diff_eq!(
+                element.iv.unwrap(),
+                prices[index].iv.unwrap(),
+                epsilon = 0.0000001
+            );
+        }
+    }
+    #[test]
+    fn test_greeks_evaluate_cf_once() {
+        let evaluations = std::sync::atomic::AtomicUsize::new(0);
+        let cf = |u: &Complex<f64>| {
+            evaluations.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            cf_functions::gauss::gauss_cf(u, -0.045, 0.3)
+        };
+        let num_u = 256;
+        get_greeks_results(
+            OptionKind::Put,
+            &GreeksSelection::new(None, ImpliedVolatilityMode::Skip).unwrap(),
+            &PricingParameters {
+                num_u,
+                scale: 0.0,
+                maturity: 1.0,
+                rate: 0.0,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            100.0,
+            &[90.0, 100.0],
+            300.0,
+            cf,
+        )
+        .unwrap();
+        assert_eq!(
+            evaluations.load(std::sync::atomic::Ordering::Relaxed),
+            num_u
+        );
+    }
+    #[test]
+    fn test_greeks_selection() {
+        let cf_parameters = CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 });
+        let pricing = PricingParameters {
+            num_u: 128,
+            scale: 10.0,
+            maturity: 1.0,
+            rate: 0.03,
+            dividend_yield: 0.0,
+            cash_dividend_shift: 0.0,
+        };
+        let selection =
+            GreeksSelection::new(Some("delta, gamma"), ImpliedVolatilityMode::Lenient).unwrap();
+        let greeks = get_greeks_results_as_json(
+            OptionKind::Put,
+            &selection,
+            &cf_parameters,
+            &pricing,
+            100.0,
+            &[100.0],
+        )
+        .unwrap();
+        assert!(greeks[0].price.is_none());
+        assert!(greeks[0].theta.is_none());
+        assert!(greeks[0].delta.is_some());
+        assert!(greeks[0].gamma.is_some());
+        //prices are still computed for the implied volatility
+        assert_abs_diff_eq!(greeks[0].iv.unwrap(), 0.3, epsilon = 0.0001);
+        assert_eq!(
+            GreeksSelection::new(Some("delta,vega"), ImpliedVolatilityMode::Skip)
+                .err()
+                .unwrap(),
+            ParameterError::new(&ErrorType::FunctionError("vega".to_string()))
+        );
+    }
+    #[test]
+    fn test_implied_volatility_errors_per_strike() {
+        let values = || {
+            vec![
+                fang_oost::GraphElement {
+                    x: 50.0,
+                    value: 49.0, //below the discounted intrinsic value
+                },
+                fang_oost::GraphElement {
+                    x: 100.0,
+                    value: 12.0,
+                },
+                fang_oost::GraphElement {
+                    x: 150.0,
+                    value: 101.0, //above the asset
+                },
+            ]
+            .into_par_iter()
+        };
+        let results = call_iv_as_json(values(), false, 100.0, 0.03, 1.0).unwrap();
+        assert_eq!(
+            results[0].iv_error,
+            Some(ImpliedVolatilityError::BelowIntrinsic)
+        );
+        assert!(results[0].iv.is_none());
+        assert!(results[1].iv.is_some());
+        assert!(results[1].iv_error.is_none());
+        assert_eq!(
+            results[2].iv_error,
+            Some(ImpliedVolatilityError::AboveUpperBound)
         );
-        assert!(results.is_err());
         assert_eq!(
-            results.unwrap_err().to_string(),
-            "Function indicator -1 does not exist."
+            call_iv_as_json(values(), true, 100.0, 0.03, 1.0).unwrap_err(),
+            throw_no_convergence_error()
         );
     }
+    #[test]
+    fn test_far_out_of_the_money_strike_keeps_curve() {
+        let get_results = |strict_iv: bool| {
+            get_option_results_as_json(
+                OptionKind::Call,
+                Sensitivity::Price,
+                ImpliedVolatilityMode::new(true, strict_iv),
+                &CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+                &PricingParameters {
+                    num_u: 256,
+                    scale: 10.0,
+                    maturity: 1.0,
+                    rate: 0.03,
+                    dividend_yield: 0.0,
+                    cash_dividend_shift: 0.0,
+                },
+                100.0,
+                &[100.0, 2000.0],
+            )
+        };
+        let results = get_results(false).unwrap();
+        assert_abs_diff_eq!(results[0].iv.unwrap(), 0.3, epsilon = 0.0001);
+        assert!(results[1].iv.is_none());
+        assert!(results[1].iv_error.is_some());
+        assert!(get_results(true).is_err());
+    }
+    #[test]
+    fn test_implied_volatility_with_dividends() {
+        let results = get_option_results_as_json(
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Lenient,
+            &CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+            &PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.04,
+                cash_dividend_shift: 0.0,
+            },
+            100.0,
+            &[90.0, 110.0],
+        )
+        .unwrap();
+        for result in results.iter() {
+            assert_abs_diff_eq!(result.iv.unwrap(), 0.3, epsilon = 0.0001);
+        }
+    }
+    #[test]
+    fn test_put_call_parity_with_dividends() {
+        let parameters = HestonParameters {
+            sigma: 0.2,
+            v0: 0.04,
+            speed: 1.5,
+            eta_v: 0.5,
+            rho: -0.5,
+        };
+        let cf_parameters = CFParameters::Heston(parameters);
+        let strikes = vec![90.0, 100.0, 110.0];
+        let (asset, t, rate, dividend_yield) = (100.0, 0.5, 0.03, 0.02);
+        let get_prices = |option_kind| {
+            get_option_results_as_json(
+                option_kind,
+                Sensitivity::Price,
+                ImpliedVolatilityMode::Skip,
+                &cf_parameters,
+                &PricingParameters {
+                    num_u: 256,
+                    scale: 10.0,
+                    maturity: t,
+                    rate,
+                    dividend_yield,
+                    cash_dividend_shift: 0.0,
+                },
+                asset,
+                &strikes,
+            )
+            .unwrap()
+        };
+        let calls = get_prices(OptionKind::Call);
+        let puts = get_prices(OptionKind::Put);
+        for ((call, put), strike) in calls.iter().zip(puts.iter()).zip(strikes.iter()) {
+            assert_abs_diff_eq!(
+                call.value - put.value,
+                asset * (-dividend_yield * t).exp() - strike * (-rate * t).exp(),
+                epsilon = 0.0001
+            );
+        }
+    }
+    #[test]
+    fn test_cash_dividend_shift() {
+        let dividends = vec![
+            CashDividend {
+                time: 0.25,
+                amount: 2.0,
+            },
+            CashDividend {
+                time: 2.0, //after maturity
+                amount: 2.0,
+            },
+        ];
+        let shift = get_cash_dividend_shift(Some(100.0), &dividends, None, 0.04, 1.0).unwrap();
+        assert_abs_diff_eq!(
+            shift,
+            (1.0 - 2.0 * (-0.01_f64).exp() / 100.0).ln(),
+            epsilon = 0.0000001
+        );
+        assert_eq!(
+            get_cash_dividend_shift(None, &dividends, None, 0.04, 0.1).unwrap(),
+            0.0
+        );
+        assert_eq!(
+            get_cash_dividend_shift(None, &dividends, None, 0.04, 1.0).unwrap_err(),
+            throw_no_exist_error("asset")
+        );
+        assert_eq!(
+            get_cash_dividend_shift(Some(1.0), &dividends, None, 0.04, 1.0)
+                .unwrap_err()
+                .to_string(),
+            "Parameter dividends out of bounds."
+        );
+    }
+    #[test]
+    fn test_black_scholes_with_cash_dividends_is_escrowed() {
+        let (asset, strike, rate, sigma, t) = (100.0, 100.0, 0.03, 0.3, 1.0);
+        let dividends = vec![CashDividend {
+            time: 0.5,
+            amount: 3.0,
+        }];
+        let shift = get_cash_dividend_shift(Some(asset), &dividends, None, rate, t).unwrap();
+        let escrowed_asset = asset - 3.0 * (-rate * 0.5_f64).exp();
+        let get_value = |option_kind, sensitivity| {
+            get_option_results_as_json(
+                option_kind,
+                sensitivity,
+                ImpliedVolatilityMode::Lenient,
+                &CFParameters::BlackScholes(BlackScholesParameters { sigma }),
+                &PricingParameters {
+                    num_u: 256,
+                    scale: 10.0,
+                    maturity: t,
+                    rate,
+                    dividend_yield: 0.0,
+                    cash_dividend_shift: shift,
+                },
+                asset,
+                &[strike],
+            )
+            .unwrap()
+            .remove(0)
+        };
+        let call = get_value(OptionKind::Call, Sensitivity::Price);
+        assert_abs_diff_eq!(
+            call.value,
+            black_scholes::call(escrowed_asset, strike, rate, sigma, t),
+            epsilon = 0.0001
+        );
+        assert_abs_diff_eq!(call.iv.unwrap(), sigma, epsilon = 0.0001);
+        let put = get_value(OptionKind::Put, Sensitivity::Price);
+        assert_abs_diff_eq!(
+            put.value,
+            black_scholes::put(escrowed_asset, strike, rate, sigma, t),
+            epsilon = 0.0001
+        );
+        let delta = get_value(OptionKind::Call, Sensitivity::Delta);
+        assert_abs_diff_eq!(
+            delta.value,
+            black_scholes::call_delta(escrowed_asset, strike, rate, sigma, t),
+            epsilon = 0.0001
+        );
+    }
+    #[test]
+    fn test_cash_dividends_shift_risk_measures() {
+        let cf_parameters = CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 });
+        let shift = -0.05;
+        let risk_metric = get_risk_measure_results_as_json(
+            &cf_parameters,
+            &PricingParameters {
+                num_u: 256,
+                scale: 5.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            0.01,
+        )
+        .unwrap();
+        let shifted_risk_metric = get_risk_measure_results_as_json(
+            &cf_parameters,
+            &PricingParameters {
+                num_u: 256,
+                scale: 5.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: shift,
+            },
+            0.01,
+        )
+        .unwrap();
+        assert_abs_diff_eq!(
+            shifted_risk_metric.value_at_risk,
+            risk_metric.value_at_risk - shift,
+            epsilon = 0.0001
+        );
+    }
+    #[test]
+    fn test_zero_rate_interpolation() {
+        let curve = vec![
+            ZeroRate {
+                tenor: 0.5,
+                rate: 0.01,
+            },
+            ZeroRate {
+                tenor: 1.0,
+                rate: 0.02,
+            },
+            ZeroRate {
+                tenor: 5.0,
+                rate: 0.04,
+            },
+        ];
+        assert_eq!(get_zero_rate(&curve, 0.25), 0.01);
+        assert_eq!(get_zero_rate(&curve, 1.0), 0.02);
+        assert_abs_diff_eq!(get_zero_rate(&curve, 3.0), 0.03, epsilon = 0.0000001);
+        assert_eq!(get_zero_rate(&curve, 10.0), 0.04);
+        assert_eq!(
+            get_effective_rate(Some(0.05), Some(&curve), 1.0),
+            Some(0.02)
+        );
+        assert_eq!(get_effective_rate(Some(0.05), None, 1.0), Some(0.05));
+        assert_eq!(get_effective_rate(None, None, 1.0), None);
+    }
+    #[test]
+    fn test_cash_dividend_shift_on_curve() {
+        let dividends = vec![CashDividend {
+            time: 0.5,
+            amount: 2.0,
+        }];
+        let curve = vec![
+            ZeroRate {
+                tenor: 0.5,
+                rate: 0.01,
+            },
+            ZeroRate {
+                tenor: 1.0,
+                rate: 0.05,
+            },
+        ];
+        let shift =
+            get_cash_dividend_shift(Some(100.0), &dividends, Some(&curve), 0.05, 1.0).unwrap();
+        assert_abs_diff_eq!(
+            shift,
+            (1.0 - 2.0 * (-0.005_f64).exp() / 100.0).ln(),
+            epsilon = 0.0000001
+        );
+    }
+    #[test]
+    fn test_with_effective_rate() {
+        let results = get_option_results_as_json(
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
+            &CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+            &PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            100.0,
+            &[100.0],
+        )
+        .unwrap();
+        assert!(results[0].effective_rate.is_none());
+        let results = with_effective_rate(results, Some(0.03));
+        assert_eq!(results[0].effective_rate, Some(0.03));
+    }
+    #[test]
+    fn test_surface_black_scholes() {
+        let parameters = SurfaceParameters {
+            maturities: vec![0.25, 1.0, 2.0],
+            rates: RateParameters {
+                rate: Some(0.03),
+                rate_curve: None,
+                dividend_yield: Some(0.01),
+                dividend_curve: None,
+                borrow_rate: None,
+                dividends: None,
+            },
+            asset: 100.0,
+            strikes: None,
+            moneyness: Some(vec![0.9, 1.0, 1.1]),
+            num_u: 8,
+            truncation_scale: None,
+            cf_parameters: CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+        };
+        let results = get_surface_results_as_json(&parameters, 10.0, 256, false).unwrap();
+        assert_eq!(results.prices.len(), 3);
+        assert!(results.effective_rates.is_none());
+        assert!(results.arbitrage_violations.is_none());
+        for (maturity, strikes) in results.maturities.iter().zip(results.strikes.iter()) {
+            assert_eq!(strikes.len(), 3);
+            assert_abs_diff_eq!(
+                strikes[1],
+                100.0 * (0.02 * maturity).exp(),
+                epsilon = 0.0000001
+            );
+        }
+        for ivs in results.implied_volatilities.iter() {
+            for iv in ivs.iter() {
+                assert_abs_diff_eq!(*iv, 0.3, epsilon = 0.001);
+            }
+        }
+        for errors in results.implied_volatility_errors.iter() {
+            assert!(errors.iter().all(|error| error.is_none()));
+        }
+    }
+    #[test]
+    fn test_surface_reports_implied_volatility_errors() {
+        let parameters = SurfaceParameters {
+            maturities: vec![1.0],
+            rates: RateParameters {
+                rate: Some(0.03),
+                rate_curve: None,
+                dividend_yield: None,
+                dividend_curve: None,
+                borrow_rate: None,
+                dividends: None,
+            },
+            asset: 100.0,
+            strikes: Some(vec![100.0, 2000.0]),
+            moneyness: None,
+            num_u: 8,
+            truncation_scale: None,
+            cf_parameters: CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+        };
+        let results = get_surface_results_as_json(&parameters, 10.0, 256, false).unwrap();
+        assert_abs_diff_eq!(results.implied_volatilities[0][0], 0.3, epsilon = 0.001);
+        assert!(results.implied_volatility_errors[0][0].is_none());
+        assert!(results.implied_volatilities[0][1].is_nan());
+        assert!(results.implied_volatility_errors[0][1].is_some());
+        let json = serde_json::to_value(&results).unwrap();
+        assert!(json["implied_volatilities"][0][1].is_null());
+        assert!(json["implied_volatility_errors"][0][1].is_string());
+    }
+    #[test]
+    fn test_surface_heston_arbitrage_free() {
+        let parameters = SurfaceParameters {
+            maturities: vec![0.5, 0.25, 1.0],
+            rates: RateParameters {
+                rate: Some(0.03),
+                rate_curve: None,
+                dividend_yield: None,
+                dividend_curve: None,
+                borrow_rate: None,
+                dividends: None,
+            },
+            asset: 100.0,
+            strikes: None,
+            moneyness: Some(vec![0.8, 0.9, 1.0, 1.1, 1.2]),
+            num_u: 8,
+            truncation_scale: None,
+            cf_parameters: CFParameters::Heston(HestonParameters {
+                sigma: 0.3,
+                v0: 0.09,
+                speed: 1.5,
+                eta_v: 0.4,
+                rho: -0.6,
+            }),
+        };
+        let results = get_surface_results_as_json(&parameters, 10.0, 256, true).unwrap();
+        assert_eq!(results.arbitrage_violations, Some(vec![]));
+    }
+    #[test]
+    fn test_short_maturity_matches_closed_form() {
+        let results = get_option_results_as_json(
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
+            &CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+            &PricingParameters {
+                num_u: 64,
+                scale: 10.0,
+                maturity: 0.01,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            100.0,
+            &[95.0, 100.0, 105.0],
+        )
+        .unwrap();
+        for element in results.iter() {
+            assert_abs_diff_eq!(
+                element.value,
+                element.closed_form.unwrap(),
+                epsilon = 0.0001
+            );
+        }
+    }
+    #[test]
+    fn test_cdf_black_scholes() {
+        let log_returns = get_log_returns(
+            None,
+            Some(&[100.0 * (-0.015_f64).exp(), 1.0, 1000000.0]),
+            Some(100.0),
+        )
+        .unwrap();
+        let results = get_cdf_results_as_json(
+            &CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+            &PricingParameters {
+                num_u: 256,
+                scale: 5.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            Some(100.0),
+            &log_returns,
+        )
+        .unwrap();
+        assert_abs_diff_eq!(results[0].log_return, -0.015, epsilon = 0.0000001);
+        assert_abs_diff_eq!(results[0].probability, 0.5, epsilon = 0.00001);
+        assert_abs_diff_eq!(results[1].price.unwrap(), 1.0, epsilon = 0.0000001);
+        assert_abs_diff_eq!(results[1].probability, 0.0, epsilon = 0.00001);
+        assert_eq!(results[2].probability, 1.0);
+    }
+    #[test]
+    fn test_quantile_black_scholes() {
+        let results = get_quantile_results_as_json(
+            &CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 }),
+            &PricingParameters {
+                num_u: 256,
+                scale: 5.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            None,
+            &[0.05, 0.5],
+        )
+        .unwrap();
+        assert!(results[0].price.is_none());
+        assert_abs_diff_eq!(
+            results[0].log_return,
+            -0.015 - 1.644854 * 0.3,
+            epsilon = 0.0001
+        );
+        assert_abs_diff_eq!(results[1].log_return, -0.015, epsilon = 0.0001);
+    }
+    #[test]
+    fn test_log_returns_require_points() {
+        assert!(get_log_returns(Some(&[0.1]), None, None).is_ok());
+        assert_eq!(
+            get_log_returns(None, Some(&[100.0]), None)
+                .unwrap_err()
+                .to_string(),
+            "Parameter asset does not exist."
+        );
+        assert!(get_log_returns(None, None, Some(100.0)).is_err());
+    }
+    #[test]
+    fn test_adaptive_results_converge() {
+        let cf_parameters = CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 });
+        let pricing = PricingParameters {
+            num_u: 0,
+            scale: 10.0,
+            maturity: 1.0,
+            rate: 0.03,
+            dividend_yield: 0.0,
+            cash_dividend_shift: 0.0,
+        };
+        let results = get_adaptive_option_results_as_json(
+            OptionKind::Call,
+            Sensitivity::Price,
+            &cf_parameters,
+            &pricing,
+            0.0001,
+            100.0,
+            &[80.0, 100.0, 120.0],
+        )
+        .unwrap();
+        assert!(results.converged);
+        assert!(results.num_u > 5 && results.num_u <= 10);
+        let results = get_implied_volatility_results(
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Lenient,
+            &pricing,
+            100.0,
+            results.results,
+        )
+        .unwrap();
+        for element in results.iter() {
+            assert!(element.convergence_error.unwrap() < 0.0001);
+            assert_abs_diff_eq!(element.iv.unwrap(), 0.3, epsilon = 0.0001);
+            assert_abs_diff_eq!(element.value, element.closed_form.unwrap(), epsilon = 0.001);
+        }
+    }
+    #[test]
+    fn test_implied_volatility_results_match_pricing_with_iv() {
+        let cf_parameters = CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 });
+        let pricing = PricingParameters {
+            num_u: 256,
+            scale: 10.0,
+            maturity: 1.0,
+            rate: 0.03,
+            dividend_yield: 0.01,
+            cash_dividend_shift: -0.02,
+        };
+        let strikes = [80.0, 100.0, 120.0];
+        for option_kind in [OptionKind::Call, OptionKind::Put] {
+            let expected = get_option_results_as_json(
+                option_kind,
+                Sensitivity::Price,
+                ImpliedVolatilityMode::Strict,
+                &cf_parameters,
+                &pricing,
+                100.0,
+                &strikes,
+            )
+            .unwrap();
+            let prices = get_option_results_as_json(
+                option_kind,
+                Sensitivity::Price,
+                ImpliedVolatilityMode::Skip,
+                &cf_parameters,
+                &pricing,
+                100.0,
+                &strikes,
+            )
+            .unwrap();
+            let results = get_implied_volatility_results(
+                option_kind,
+                Sensitivity::Price,
+                ImpliedVolatilityMode::Strict,
+                &pricing,
+                100.0,
+                prices,
+            )
+            .unwrap();
+            for (result, expected) in results.iter().zip(expected.iter()) {
+                assert_abs_diff_eq!(result.iv.unwrap(), expected.iv.unwrap(), epsilon = 1e-10);
+                assert_eq!(result.closed_form, expected.closed_form);
+            }
+        }
+    }
+    #[test]
+    fn test_implied_volatility_results_rejects_vega() {
+        let pricing = PricingParameters {
+            num_u: 256,
+            scale: 10.0,
+            maturity: 1.0,
+            rate: 0.03,
+            dividend_yield: 0.0,
+            cash_dividend_shift: 0.0,
+        };
+        assert!(get_implied_volatility_results(
+            OptionKind::Call,
+            Sensitivity::Vega,
+            ImpliedVolatilityMode::Lenient,
+            &pricing,
+            100.0,
+            vec![],
+        )
+        .is_err());
+    }
+    #[test]
+    fn test_adaptive_results_stop_at_largest_num_u() {
+        let cf_parameters = CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 });
+        let results = get_adaptive_option_results_as_json(
+            OptionKind::Put,
+            Sensitivity::Price,
+            &cf_parameters,
+            &PricingParameters {
+                num_u: 0,
+                scale: 10.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            0.0,
+            100.0,
+            &[100.0],
+        )
+        .unwrap();
+        assert!(!results.converged);
+        assert_eq!(results.num_u, 10);
+        assert!(results.results[0].convergence_error.is_some());
+    }
+    #[test]
+    fn test_arbitrage_violations_black_scholes() {
+        let cf_parameters = CFParameters::BlackScholes(BlackScholesParameters { sigma: 0.3 });
+        let pricing = PricingParameters {
+            num_u: 256,
+            scale: 10.0,
+            maturity: 1.0,
+            rate: 0.03,
+            dividend_yield: 0.01,
+            cash_dividend_shift: -0.02,
+        };
+        let strikes = vec![110.0, 60.0, 80.0, 100.0, 90.0, 140.0];
+        let puts: Vec<f64> = get_option_results_as_json(
+            OptionKind::Put,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
+            &cf_parameters,
+            &pricing,
+            100.0,
+            &strikes,
+        )
+        .unwrap()
+        .iter()
+        .map(|element| element.value)
+        .collect();
+        let violations = get_arbitrage_violations_as_json(
+            &cf_parameters,
+            &pricing,
+            100.0,
+            &strikes,
+            Some(&puts),
+        )
+        .unwrap();
+        assert!(violations.is_empty());
+        //parity fails once the puts are shifted
+        let violations = get_arbitrage_violations_as_json(
+            &cf_parameters,
+            &pricing,
+            100.0,
+            &strikes,
+            Some(&puts.iter().map(|put| put - 1.0).collect::<Vec<f64>>()),
+        )
+        .unwrap();
+        assert_eq!(violations.len(), strikes.len());
+        assert!(violations
+            .iter()
+            .all(|violation| violation.condition == arbitrage::ArbitrageCondition::PutCallParity));
+    }
+    #[test]
+    fn test_closed_form_only_for_black_scholes() {
+        let parameters = HestonParameters {
+            sigma: 0.2,
+            v0: 0.04,
+            speed: 1.5,
+            eta_v: 0.5,
+            rho: -0.5,
+        };
+        let results = get_option_results_as_json(
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
+            &CFParameters::Heston(parameters),
+            &PricingParameters {
+                num_u: 256,
+                scale: 10.0,
+                maturity: 1.0,
+                rate: 0.03,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            100.0,
+            &[100.0],
+        )
+        .unwrap();
+        assert!(results[0].closed_form.is_none());
+    }
+    #[test]
+    fn test_monte_carlo() {
+        // ../../techdoc/OptionCalculation.Rnw
+        let parameters = MertonParameters {
+            sigma: 0.2,
+            lambda: 0.5,
+            mu_l: -0.05,
+            sig_l: 0.1,
+            speed: 0.3,
+            v0: 0.9,
+            eta_v: 0.2,
+            rho: -0.5,
+        };
+        let strikes = vec![50.0];
+        let num_u: usize = 256;
+        let t = 1.0;
+        let rate = 0.03;
+        let asset = 50.0;
+        let results = get_option_results_as_json(
+            OptionKind::Call,
+            Sensitivity::Price,
+            ImpliedVolatilityMode::Skip,
+            &CFParameters::Merton(parameters),
+            &PricingParameters {
+                num_u,
+                scale: 10.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            asset,
+            &strikes,
+        )
+        .unwrap();
+        //MC price is 4.793274
+        assert!(results[0].value > 4.781525);
+        assert!(results[0].value < 4.805023);
+    }
+    #[test]
+    fn test_risk_measures() {
+        //https://github.com/phillyfan1138/levy-functions/issues/27
+        let parameters = MertonParameters {
+            sigma: 0.3183,
+            lambda: 0.204516,
+            mu_l: -0.302967,
+            sig_l: 0.220094,
+            speed: 2.6726,
+            v0: 0.237187,
+            eta_v: 0.0,
+            rho: -0.182754,
+        };
+        let num_u: usize = 256;
+        let t = 0.187689;
+        let rate = 0.004;
+        let quantile = 0.01;
+        let results = get_risk_measure_results_as_json(
+            &CFParameters::Merton(parameters),
+            &PricingParameters {
+                num_u,
+                scale: 5.0,
+                maturity: t,
+                rate,
+                dividend_yield: 0.0,
+                cash_dividend_shift: 0.0,
+            },
+            quantile,
+        )
+        .unwrap();
+        //converged value, with num_u of 2^13 and a wider range, is 0.261534
+        assert_abs_diff_eq!(results.value_at_risk, 0.261534, epsilon = 0.00001);
+    }
 }
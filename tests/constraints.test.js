@@ -45,6 +45,18 @@ describe('risk_measures', () => {
             return expect(response.mu_l).toBeTruthy()
         })
 
+    })
+    it('returns constraints for bates', () => {
+        return fetch(
+            `http://127.0.0.1:${port}/v2/bates/parameters/parameter_ranges`,
+            { method: 'GET', headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            return Promise.all([
+                expect(response.mu_l).toBeTruthy(),
+                expect(response.eta_v).toBeTruthy(),
+            ])
+        })
+
     })
     it('returns constraints for market', () => {
         return fetch(
@@ -59,6 +59,239 @@ describe('option prices', () => {
             ])
         })
     })
+    it('returns closed form price for black scholes', () => {
+        const body = {
+            num_u: 8,
+            rate: 0.1,
+            maturity: 0.5,
+            asset: 38,
+            cf_parameters: { sigma: 0.5 },
+            strikes: [40],
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/blackscholes/calculator/call/price`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            return Promise.all([
+                expect(response[0].value).toBeDefined(),
+                expect(response[0].closed_form).toBeCloseTo(response[0].value, 3)
+            ])
+        })
+
+    })
+    it('accepts a dividend yield and borrow rate', () => {
+        const body = {
+            num_u: 8,
+            rate: 0.1,
+            dividend_yield: 0.02,
+            borrow_rate: 0.01,
+            maturity: 0.5,
+            asset: 38,
+            cf_parameters: { sigma: 0.5 },
+            strikes: [40],
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/blackscholes/calculator/put/price`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            return expect(response[0].closed_form).toBeCloseTo(response[0].value, 3)
+        })
+
+    })
+    it('reports the effective rate from a rate curve', () => {
+        const body = {
+            num_u: 8,
+            rate_curve: [{ tenor: 0.25, rate: 0.02 }, { tenor: 1.0, rate: 0.04 }],
+            maturity: 0.5,
+            asset: 38,
+            cf_parameters: { sigma: 0.5 },
+            strikes: [40],
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/blackscholes/calculator/call/price`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            return expect(response[0].effective_rate).toBeCloseTo(0.02 + 0.02 / 3, 6)
+        })
+
+    })
+    it('reports implied volatility failures per strike', () => {
+        const body = {
+            num_u: 8,
+            rate: 0.03,
+            maturity: 1.0,
+            asset: 100,
+            cf_parameters: { sigma: 0.3 },
+            strikes: [100, 2000]
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/blackscholes/calculator/call/price?include_implied_volatility=true`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            expect(response[0].iv).toBeCloseTo(0.3, 3)
+            return expect(response[1].iv_error).toBeDefined()
+        })
+    })
+    it('rejects implied volatility for vega', () => {
+        const body = {
+            num_u: 8,
+            rate: 0.03,
+            maturity: 1.0,
+            asset: 100,
+            cf_parameters: { sigma: 0.3 },
+            strikes: [100]
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/blackscholes/calculator/call/vega?include_implied_volatility=true`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => {
+            expect(res.status).toEqual(400)
+            return res.json()
+        }).then(response => {
+            return expect(response.err).toEqual('Option include_implied_volatility is not supported for Vega.')
+        })
+    })
+    it('returns all greeks in one call', () => {
+        const body = {
+            num_u: 8,
+            rate: 0.03,
+            maturity: 1.0,
+            asset: 100,
+            cf_parameters: { sigma: 0.3 },
+            strikes: [90, 100]
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/blackscholes/calculator/call/greeks?include_implied_volatility=true`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            expect(response.length).toEqual(2)
+            expect(response[1].delta).toBeCloseTo(0.5987, 3)
+            return expect(response[1].iv).toBeCloseTo(0.3, 3)
+        })
+    })
+    it('returns only the selected greeks', () => {
+        const body = {
+            num_u: 8,
+            rate: 0.03,
+            maturity: 1.0,
+            asset: 100,
+            cf_parameters: { sigma: 0.3 },
+            strikes: [90, 100]
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/blackscholes/calculator/call/greeks?sensitivities=delta,gamma`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            expect(response[1].price).toBeUndefined()
+            expect(response[1].theta).toBeUndefined()
+            return expect(response[1].delta).toBeCloseTo(0.5987, 3)
+        })
+    })
+    it('returns a volatility surface across maturities', () => {
+        const body = {
+            num_u: 8,
+            rate: 0.03,
+            maturities: [0.5, 1.0],
+            asset: 100,
+            cf_parameters: { sigma: 0.3 },
+            moneyness: [0.9, 1.0, 1.1]
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/blackscholes/surface`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            expect(response.prices.length).toEqual(2)
+            expect(response.strikes[1].length).toEqual(3)
+            expect(response.implied_volatility_errors[1][1]).toBeNull()
+            return expect(response.implied_volatilities[1][1]).toBeCloseTo(0.3, 3)
+        })
+    })
+    it('chooses num_u automatically', () => {
+        const body = {
+            num_u: 'auto',
+            tolerance: 0.0001,
+            rate: 0.03,
+            maturity: 1.0,
+            asset: 100,
+            cf_parameters: { sigma: 0.3 },
+            strikes: [90, 100, 110]
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/blackscholes/calculator/call/price`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            expect(response.converged).toEqual(true)
+            expect(response.results[1].convergence_error).toBeLessThan(0.0001)
+            return expect(response.results[1].value).toBeCloseTo(response.results[1].closed_form, 3)
+        })
+    })
+    it('returns no arbitrage violations for a black scholes surface', () => {
+        const body = {
+            num_u: 8,
+            rate: 0.03,
+            maturities: [0.5, 1.0],
+            asset: 100,
+            cf_parameters: { sigma: 0.3 },
+            moneyness: [0.9, 1.0, 1.1]
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/blackscholes/surface?check_arbitrage=true`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            return expect(response.arbitrage_violations).toEqual([])
+        })
+    })
+    it('checks put prices for arbitrage alongside calculator results', () => {
+        const body = {
+            num_u: 8,
+            rate: 0.03,
+            maturity: 1.0,
+            asset: 100,
+            cf_parameters: { sigma: 0.3 },
+            strikes: [90, 100, 110]
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/blackscholes/calculator/put/price?check_arbitrage=true`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            expect(response.results.length).toEqual(3)
+            expect(response.num_u).toEqual(8)
+            expect(response.converged).toEqual(true)
+            return expect(response.arbitrage_violations).toEqual([])
+        })
+    })
+    it('returns the cumulative distribution at prices', () => {
+        const body = {
+            num_u: 8,
+            rate: 0.03,
+            maturity: 1.0,
+            asset: 100,
+            cf_parameters: { sigma: 0.3 },
+            prices: [80, 100, 120]
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/blackscholes/cdf`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            expect(response.length).toEqual(3)
+            return expect(response[0].probability).toBeCloseTo(0.2439, 3)
+        })
+    })
+    it('returns quantiles at probabilities', () => {
+        const body = {
+            num_u: 8,
+            rate: 0.03,
+            maturity: 1.0,
+            cf_parameters: { sigma: 0.3 },
+            probabilities: [0.05, 0.5]
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/blackscholes/quantile`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            return expect(response[1].log_return).toBeCloseTo(-0.015, 3)
+        })
+    })
     it('returns error if not all parameters included', () => {
         const body = {
             num_u: 8,
@@ -75,4 +308,20 @@ describe('option prices', () => {
             return expect(response.err).toEqual("Parameter strikes does not exist.")
         })
     })
+    it('returns error if body model does not match path', () => {
+        const body = {
+            num_u: 8,
+            rate: 0.1,
+            maturity: 0.5,
+            asset: 38,
+            cf_parameters: { model: "merton", sigma: 0.5, speed: 0.1, v0: 0.2, eta_v: 0.1, rho: -0.5, lambda: 0.5, mu_l: -0.05, sig_l: 0.3 },
+            strikes: [100]
+        }
+        return fetch(
+            `http://127.0.0.1:${port}/v2/heston/calculator/put/price`,
+            { method: 'POST', body: JSON.stringify(body), headers: { 'Content-Type': 'application/json' }, }
+        ).then(res => res.json()).then(response => {
+            return expect(response.err).toEqual("Model merton in body does not match model heston in path.")
+        })
+    })
 })
\ No newline at end of file